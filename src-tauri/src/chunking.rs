@@ -0,0 +1,409 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::code_analysis::extract_functions_from;
+use crate::config::{find_model, load_model_registry};
+use crate::error::AppError;
+use crate::file_ops::{
+    format_loaded, format_output, max_file_size, prepare_loaded, project_tree, visible_files,
+    CopyRequest,
+};
+use crate::jobs::JobContext;
+use crate::read_pipeline::{load_files, FileBody, LoadedFile};
+use crate::secrets::ensure_confirmed;
+use crate::token_counter::model_tokenizer;
+use crate::tokenizer::Tokenizer;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChunkRequest {
+    pub request: CopyRequest,
+    pub model: String,
+    pub max_tokens: u32,
+}
+
+/// A file, or a range of its lines when it had to be split, in a part's manifest
+#[derive(Debug, Serialize, Clone)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub line_start: Option<usize>,
+    pub line_end: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Chunk {
+    pub part: usize,
+    pub total_parts: usize,
+    pub tokens: u32,
+    /// Set when a single function is larger than the limit on its own
+    pub over_limit: bool,
+    pub files: Vec<ManifestEntry>,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ChunkResult {
+    pub model: String,
+    pub max_tokens: u32,
+    pub chunks: Vec<Chunk>,
+}
+
+/// A formatted unit that is never split further
+struct Piece {
+    file: LoadedFile,
+    entry: ManifestEntry,
+    tokens: u32,
+}
+
+/// Split the formatted selection into numbered parts under a token limit
+pub fn chunk_selection(chunk: &ChunkRequest) -> Result<ChunkResult, AppError> {
+    let registry = load_model_registry()?;
+    let tokenizer = model_tokenizer(find_model(&registry, &chunk.model)?)?;
+
+    Ok(ChunkResult {
+        model: chunk.model.clone(),
+        max_tokens: chunk.max_tokens,
+        chunks: chunk_with(&chunk.request, chunk.max_tokens, tokenizer.as_ref())?,
+    })
+}
+
+/// Group files into parts, splitting at file boundaries first and at function
+/// boundaries from `extract_functions` when one file does not fit in a part.
+/// The project tree, when requested, opens the first part.
+pub fn chunk_with(
+    request: &CopyRequest,
+    max_tokens: u32,
+    tokenizer: &dyn Tokenizer,
+) -> Result<Vec<Chunk>, AppError> {
+    let base_path = Path::new(&request.base_path);
+    let max_size = max_file_size(request);
+
+    let files = visible_files(request)?;
+    let mut loaded = load_files(&files, base_path, max_size, &JobContext::detached())?;
+    let findings = prepare_loaded(&mut loaded, request)?;
+    if let Some(scan) = &request.secret_scan {
+        ensure_confirmed(&findings, scan, request.secrets_confirmed)?;
+    }
+
+    // Leave room for the part header, counted with wide part numbers
+    let header_tokens = tokenizer.count(&part_header(999, 999, &[]));
+    let limit = max_tokens.saturating_sub(header_tokens);
+    if limit == 0 {
+        return Err(AppError::invalid(
+            "Token limit is too small to fit a part header",
+        ));
+    }
+
+    let mut pieces = Vec::new();
+    for file in loaded {
+        pieces.extend(split_file(
+            file,
+            &request.format,
+            max_size,
+            limit,
+            tokenizer,
+        )?);
+    }
+
+    // The tree comes out of the first part's budget
+    let tree = project_tree(request)?;
+    let tree_tokens = match tree.as_deref() {
+        Some(tree) => tokenizer.count(&format_output(&[], Some(tree), &request.format, max_size)?),
+        None => 0,
+    };
+
+    let mut groups: Vec<Vec<Piece>> = Vec::new();
+    let mut current = Vec::new();
+    let mut used = tree_tokens;
+
+    for piece in pieces {
+        let cost = piece.tokens + tokenizer.count(&manifest_line(&piece.entry));
+        if !current.is_empty() && used + cost > limit {
+            groups.push(std::mem::take(&mut current));
+            used = 0;
+        }
+        used += cost;
+        current.push(piece);
+    }
+    if !current.is_empty() || (groups.is_empty() && tree.is_some()) {
+        groups.push(current);
+    }
+
+    let total_parts = groups.len();
+    groups
+        .into_iter()
+        .enumerate()
+        .map(|(i, group)| {
+            let (files, entries): (Vec<LoadedFile>, Vec<ManifestEntry>) = group
+                .into_iter()
+                .map(|piece| (piece.file, piece.entry))
+                .unzip();
+
+            let tree = if i == 0 { tree.as_deref() } else { None };
+            let content = format!(
+                "{}{}",
+                part_header(i + 1, total_parts, &entries),
+                format_output(&files, tree, &request.format, max_size)?
+            );
+            let tokens = tokenizer.count(&content);
+
+            Ok(Chunk {
+                part: i + 1,
+                total_parts,
+                tokens,
+                over_limit: tokens > max_tokens,
+                files: entries,
+                content,
+            })
+        })
+        .collect()
+}
+
+fn split_file(
+    file: LoadedFile,
+    format: &str,
+    max_size: u64,
+    limit: u32,
+    tokenizer: &dyn Tokenizer,
+) -> Result<Vec<Piece>, AppError> {
+    let whole = format_loaded(std::slice::from_ref(&file), format, max_size)?;
+    let tokens = tokenizer.count(&whole);
+
+    let content = match &file.body {
+        FileBody::Text(content) if tokens > limit => content.clone(),
+        _ => {
+            let entry = ManifestEntry {
+                path: file.relative_path.clone(),
+                line_start: file.lines.map(|(start, _)| start),
+                line_end: file.lines.map(|(_, end)| end),
+            };
+            return Ok(vec![Piece {
+                file,
+                entry,
+                tokens,
+            }]);
+        }
+    };
+
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let boundaries = function_boundaries(&file, &content, &lines);
+    // A selected slice is split further in lines of the whole file
+    let offset = file.lines.map_or(0, |(start, _)| start - 1);
+
+    // Cost of the file header, fences and manifest line around an empty body
+    let wrapper = tokenizer.count(&format_loaded(
+        &[with_body(
+            &file,
+            String::new(),
+            (offset + lines.len(), offset + lines.len()),
+        )],
+        format,
+        max_size,
+    )?) + tokenizer.count(&manifest_line(&ManifestEntry {
+        path: file.relative_path.clone(),
+        line_start: Some(offset + lines.len()),
+        line_end: Some(offset + lines.len()),
+    }));
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let mut start = 0;
+    let mut used = wrapper;
+
+    for (i, &segment_start) in boundaries.iter().enumerate() {
+        let segment_end = boundaries.get(i + 1).copied().unwrap_or(lines.len());
+        let segment_tokens = tokenizer.count(&lines[segment_start..segment_end].concat());
+
+        if segment_start > start && used + segment_tokens > limit {
+            ranges.push((start, segment_start));
+            start = segment_start;
+            used = wrapper;
+        }
+        used += segment_tokens;
+    }
+    ranges.push((start, lines.len()));
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let range = (offset + start + 1, offset + end);
+            let piece_file = with_body(&file, lines[start..end].concat(), range);
+            let block = format_loaded(std::slice::from_ref(&piece_file), format, max_size)?;
+
+            Ok(Piece {
+                file: piece_file,
+                entry: ManifestEntry {
+                    path: file.relative_path.clone(),
+                    line_start: Some(range.0),
+                    line_end: Some(range.1),
+                },
+                tokens: tokenizer.count(&block),
+            })
+        })
+        .collect()
+}
+
+/// Zero-based lines where a top-level function starts, always including 0.
+///
+/// A boundary is moved up over the comments and attributes directly above the
+/// function so they stay with it.
+fn function_boundaries(file: &LoadedFile, content: &str, lines: &[&str]) -> Vec<usize> {
+    let path = file.path.to_string_lossy();
+    let mut functions = extract_functions_from(&path, content).unwrap_or_default();
+    functions.sort_by_key(|f| f.line_start);
+
+    let mut boundaries = vec![0];
+    let mut last_end = 0;
+
+    for function in functions {
+        // Functions nested in one already kept are not boundaries
+        if function.line_start <= last_end {
+            continue;
+        }
+        last_end = function.line_end;
+
+        let mut start = function.line_start.saturating_sub(1);
+        while start > 0 && is_preamble(lines[start - 1]) {
+            start -= 1;
+        }
+        if start > *boundaries.last().unwrap() {
+            boundaries.push(start);
+        }
+    }
+
+    boundaries
+}
+
+fn is_preamble(line: &str) -> bool {
+    let line = line.trim_start();
+    ["//", "/*", "*", "#", "@"]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+}
+
+fn with_body(file: &LoadedFile, content: String, lines: (usize, usize)) -> LoadedFile {
+    LoadedFile {
+        path: file.path.clone(),
+        relative_path: file.relative_path.clone(),
+        size: content.len() as u64,
+        body: FileBody::Text(content),
+        history: file.history.clone(),
+        lines: Some(lines),
+    }
+}
+
+fn part_header(part: usize, total_parts: usize, entries: &[ManifestEntry]) -> String {
+    let mut header = format!("Part {} of {}\nFiles in this part:\n", part, total_parts);
+    for entry in entries {
+        header.push_str(&manifest_line(entry));
+    }
+    header.push('\n');
+    header
+}
+
+fn manifest_line(entry: &ManifestEntry) -> String {
+    match (entry.line_start, entry.line_end) {
+        (Some(start), Some(end)) => format!("- {} (lines {}-{})\n", entry.path, start, end),
+        _ => format!("- {}\n", entry.path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_tree::TreeOptions;
+    use crate::tokenizer::bpe_tokenizer;
+    use std::fs;
+
+    #[test]
+    fn test_chunks_split_at_files_and_functions() {
+        let dir = std::env::temp_dir().join(format!("filesurfer_chunks_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut large = String::new();
+        for i in 0..12 {
+            large.push_str(&format!(
+                "/// Function number {}\nfn function_{}() {{\n    let value = {};\n    println!(\"{{}}\", value);\n}}\n\n",
+                i, i, i
+            ));
+        }
+        fs::write(dir.join("small.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.join("large.rs"), &large).unwrap();
+
+        let request = CopyRequest {
+            files: vec![
+                dir.join("small.rs").to_string_lossy().to_string(),
+                dir.join("large.rs").to_string_lossy().to_string(),
+            ],
+            base_path: dir.to_string_lossy().to_string(),
+            format: "markdown".to_string(),
+            ..Default::default()
+        };
+        let tokenizer = bpe_tokenizer("cl100k_base").unwrap();
+        let chunks = chunk_with(&request, 120, &tokenizer).unwrap();
+
+        assert!(chunks.len() > 2);
+        assert_eq!(chunks[0].files[0].path, "small.rs");
+        assert!(chunks[0]
+            .content
+            .starts_with(&format!("Part 1 of {}", chunks.len())));
+        for chunk in &chunks {
+            assert!(chunk.tokens <= 120 && !chunk.over_limit);
+        }
+
+        // Every split starts at a function's doc comment
+        let large_parts: Vec<&ManifestEntry> = chunks
+            .iter()
+            .flat_map(|c| &c.files)
+            .filter(|e| e.path == "large.rs")
+            .collect();
+        assert_eq!(large_parts[0].line_start, Some(1));
+        for pair in large_parts.windows(2) {
+            let next = pair[1].line_start.unwrap();
+            assert_eq!(pair[0].line_end, Some(next - 1));
+            assert_eq!((next - 1) % 6, 0);
+        }
+        assert_eq!(large_parts.last().unwrap().line_end, Some(72));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn test_tree_opens_first_part() {
+        let dir =
+            std::env::temp_dir().join(format!("filesurfer_chunk_tree_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["one.rs", "two.rs", "three.rs"] {
+            fs::write(dir.join(name), "fn main() {\n    println!(\"hello\");\n}\n").unwrap();
+        }
+
+        let request = CopyRequest {
+            files: ["one.rs", "two.rs", "three.rs"]
+                .iter()
+                .map(|name| dir.join(name).to_string_lossy().to_string())
+                .collect(),
+            base_path: dir.to_string_lossy().to_string(),
+            format: "markdown".to_string(),
+            tree: Some(TreeOptions {
+                max_depth: None,
+                show_sizes: false,
+            }),
+            ..Default::default()
+        };
+        let tokenizer = bpe_tokenizer("cl100k_base").unwrap();
+        let chunks = chunk_with(&request, 90, &tokenizer).unwrap();
+
+        assert!(chunks.len() > 1);
+        let (header, body) = chunks[0].content.split_once("\n\n").unwrap();
+        assert!(header.starts_with("Part 1 of"));
+        assert!(body.starts_with("Project structure"));
+        assert!(body.contains("three.rs"));
+        for chunk in &chunks[1..] {
+            assert!(!chunk.content.contains("Project structure"));
+        }
+        for chunk in &chunks {
+            assert!(!chunk.over_limit);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
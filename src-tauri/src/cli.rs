@@ -0,0 +1,502 @@
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::code_analysis::{detect_dependencies, extract_todos};
+use crate::config::{load_config, load_model_registry, AppConfig};
+use crate::error::{AppError, ErrorKind};
+use crate::file_ops::{read_file_contents, scan_directory, CopyRequest, FileNode};
+use crate::git::{DiffSettings, GitMetadata};
+use crate::mcp::McpServer;
+use crate::project_tree::TreeOptions;
+use crate::skeleton::SkeletonOptions;
+use crate::token_counter::estimate_tokens;
+
+const USAGE: &str = "\
+Usage: filesurfer <command> [options]
+
+Commands:
+  scan <dir>              List the files the tree would show
+  pack <dir> [files...]   Build a context bundle (all visible files by default)
+  tokens [file]           Count tokens in a file, or stdin when omitted
+  deps <file>...          List the imports of each file
+  todos <file>...         List TODO/FIXME/NOTE/HACK/XXX comments
+  mcp [roots...]          Serve MCP tools over stdio, limited to the given roots
+                          (the current directory by default)
+
+Options:
+  -o, --output <file>     Write to a file instead of stdout
+  --format <format>       markdown, json, xml or diff (pack)
+  --base <ref>            Compare with this ref instead of HEAD (pack --format diff)
+  --context <lines>       Context lines around each change (pack --format diff)
+  --full                  Show whole files with the changes marked (pack --format diff)
+  --history               Add each file's last commit (pack)
+  --blame                 Add the last commit and per-line blame (pack)
+  --tree                  Start with a tree of the project (pack)
+  --tree-depth <n>        Collapse the tree below this depth (pack)
+  --tree-sizes            Show file sizes in the tree (pack)
+  --skeleton              Show signatures only, with function bodies elided (pack)
+  --focus <file>          Keep this file in full with --skeleton, repeatable (pack)
+  --max-size <mb>         Skip files larger than this (pack)
+  --ignore <pattern>      Extra gitignore-style pattern, repeatable (scan, pack)
+  --model <id>            Print only this model's count (tokens)
+  --json                  Print JSON instead of text
+  --allow-secrets         Pack even if secret scanning is in block mode and finds some
+
+Exit codes:
+  0  success            3  not found          5  not a git repository
+  1  other failure      4  access denied      6  invalid input
+  2  usage error        7  secrets found (block mode; pass --allow-secrets)
+";
+
+const COMMANDS: &[&str] = &["scan", "pack", "tokens", "deps", "todos", "mcp"];
+
+enum CliError {
+    Usage(String),
+    Failed(AppError),
+}
+
+impl From<AppError> for CliError {
+    fn from(error: AppError) -> Self {
+        CliError::Failed(error)
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    positional: Vec<String>,
+    output: Option<String>,
+    format: Option<String>,
+    max_size_mb: Option<u64>,
+    ignore: Vec<String>,
+    model: Option<String>,
+    json: bool,
+    allow_secrets: bool,
+    base: Option<String>,
+    context_lines: Option<u32>,
+    full_file: bool,
+    git_metadata: GitMetadata,
+    tree: Option<TreeOptions>,
+    skeleton: Option<SkeletonOptions>,
+}
+
+/// Run a headless subcommand and return its exit code, or `None` when `args`
+/// do not start with one and the GUI should open instead
+pub fn run(args: &[String]) -> Option<i32> {
+    let command = args.first()?.as_str();
+
+    if matches!(command, "help" | "-h" | "--help") {
+        print!("{}", USAGE);
+        return Some(0);
+    }
+    if !COMMANDS.contains(&command) {
+        return None;
+    }
+
+    let config = load_config().unwrap_or_default();
+    let result = parse_options(&args[1..]).and_then(|options| {
+        let output = execute(command, &options, &config)?;
+        write_output(&output, options.output.as_deref())
+    });
+
+    Some(match result {
+        Ok(()) => 0,
+        Err(CliError::Usage(message)) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            2
+        }
+        Err(CliError::Failed(error)) => {
+            eprintln!("error: {}", error);
+            exit_code(error.kind)
+        }
+    })
+}
+
+/// Exit code for a failed command, so scripts can tell failures apart
+fn exit_code(kind: ErrorKind) -> i32 {
+    match kind {
+        ErrorKind::NotFound => 3,
+        ErrorKind::PermissionDenied | ErrorKind::OutsideWorkspace => 4,
+        ErrorKind::NotAGitRepo => 5,
+        ErrorKind::InvalidInput => 6,
+        ErrorKind::SecretsFound => 7,
+        ErrorKind::Cancelled | ErrorKind::Io | ErrorKind::Internal => 1,
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, CliError> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| CliError::Usage(format!("{} needs a value", name)))
+        };
+
+        match arg.as_str() {
+            "-o" | "--output" => options.output = Some(value(arg)?),
+            "--format" => options.format = Some(value(arg)?),
+            "--max-size" => {
+                let size = value(arg)?;
+                options.max_size_mb = Some(
+                    size.parse()
+                        .map_err(|_| CliError::Usage(format!("Invalid size: {}", size)))?,
+                );
+            }
+            "--ignore" => options.ignore.push(value(arg)?),
+            "--model" => options.model = Some(value(arg)?),
+            "--json" => options.json = true,
+            "--allow-secrets" => options.allow_secrets = true,
+            "--base" => options.base = Some(value(arg)?),
+            "--context" => {
+                let lines = value(arg)?;
+                options.context_lines = Some(
+                    lines
+                        .parse()
+                        .map_err(|_| CliError::Usage(format!("Invalid line count: {}", lines)))?,
+                );
+            }
+            "--full" => options.full_file = true,
+            "--history" => options.git_metadata = GitMetadata::LastCommit,
+            "--blame" => options.git_metadata = GitMetadata::Blame,
+            "--tree" => {
+                options.tree.get_or_insert_with(TreeOptions::default);
+            }
+            "--tree-depth" => {
+                let depth = value(arg)?;
+                options
+                    .tree
+                    .get_or_insert_with(TreeOptions::default)
+                    .max_depth = Some(
+                    depth
+                        .parse()
+                        .map_err(|_| CliError::Usage(format!("Invalid depth: {}", depth)))?,
+                );
+            }
+            "--tree-sizes" => {
+                options
+                    .tree
+                    .get_or_insert_with(TreeOptions::default)
+                    .show_sizes = true;
+            }
+            "--skeleton" => {
+                options
+                    .skeleton
+                    .get_or_insert_with(SkeletonOptions::default);
+            }
+            "--focus" => {
+                let file = value(arg)?;
+                options
+                    .skeleton
+                    .get_or_insert_with(SkeletonOptions::default)
+                    .focus_files
+                    .push(file);
+            }
+            flag if flag.starts_with('-') && flag != "-" => {
+                return Err(CliError::Usage(format!("Unknown option: {}", flag)))
+            }
+            _ => options.positional.push(arg.clone()),
+        }
+    }
+
+    Ok(options)
+}
+
+fn execute(command: &str, options: &Options, config: &AppConfig) -> Result<String, CliError> {
+    match command {
+        "scan" => scan(options, config),
+        "pack" => pack(options, config),
+        "tokens" => tokens(options),
+        "deps" => deps(options),
+        "todos" => todos(options),
+        "mcp" => mcp(options),
+        _ => Err(CliError::Usage(format!("Unknown command: {}", command))),
+    }
+}
+
+fn scan(options: &Options, config: &AppConfig) -> Result<String, CliError> {
+    let dir = single_path(options, "scan needs a directory")?;
+    let nodes = scan_directory(dir, ignore_patterns(config, options))?;
+
+    if options.json {
+        return to_json(&nodes);
+    }
+
+    let mut files = Vec::new();
+    collect_files(&nodes, &mut files);
+    Ok(files
+        .iter()
+        .map(|file| relative_to(file, dir))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn pack(options: &Options, config: &AppConfig) -> Result<String, CliError> {
+    let (dir, selected) = options
+        .positional
+        .split_first()
+        .ok_or_else(|| CliError::Usage("pack needs a directory".to_string()))?;
+    let ignore_patterns = ignore_patterns(config, options);
+
+    let files = if selected.is_empty() {
+        let mut files = Vec::new();
+        collect_files(&scan_directory(dir, ignore_patterns.clone())?, &mut files);
+        files
+    } else {
+        selected
+            .iter()
+            .map(|file| Path::new(dir).join(file).to_string_lossy().to_string())
+            .collect()
+    };
+
+    let request = CopyRequest {
+        files,
+        base_path: dir.clone(),
+        format: options
+            .format
+            .clone()
+            .unwrap_or_else(|| config.output_format.clone()),
+        max_file_size_mb: Some(options.max_size_mb.unwrap_or(config.max_file_size_mb)),
+        ignore_patterns,
+        secret_scan: Some(config.secret_scan.clone()),
+        secrets_confirmed: options.allow_secrets,
+        diff: Some(DiffSettings {
+            base: options
+                .base
+                .clone()
+                .unwrap_or_else(|| config.diff.base.clone()),
+            context_lines: options.context_lines.unwrap_or(config.diff.context_lines),
+            full_file: options.full_file || config.diff.full_file,
+        }),
+        git_metadata: options.git_metadata,
+        tree: options.tree.clone(),
+        // Focus files are given relative to the directory like selected ones
+        skeleton: options.skeleton.as_ref().map(|skeleton| SkeletonOptions {
+            focus_files: skeleton
+                .focus_files
+                .iter()
+                .map(|file| Path::new(dir).join(file).to_string_lossy().to_string())
+                .collect(),
+        }),
+        selections: Vec::new(),
+    };
+
+    Ok(read_file_contents(&request)?)
+}
+
+fn tokens(options: &Options) -> Result<String, CliError> {
+    let content = match options.positional.as_slice() {
+        [] => {
+            let mut content = String::new();
+            io::stdin().read_to_string(&mut content).map_err(|e| {
+                AppError::new(ErrorKind::Io, format!("Failed to read stdin: {}", e))
+            })?;
+            content
+        }
+        [file] => fs::read_to_string(file).map_err(|e| AppError::io(e, "Failed to read", file))?,
+        _ => return Err(CliError::Usage("tokens takes at most one file".to_string())),
+    };
+
+    let estimate = estimate_tokens(&content, &load_model_registry()?.models)?;
+
+    if let Some(model) = &options.model {
+        let found = estimate
+            .models
+            .iter()
+            .find(|m| &m.model == model)
+            .ok_or_else(|| AppError::not_found(format!("Unknown model: {}", model)))?;
+        return if options.json {
+            to_json(found)
+        } else {
+            Ok(found.tokens.to_string())
+        };
+    }
+
+    if options.json {
+        return to_json(&estimate);
+    }
+
+    Ok(estimate
+        .models
+        .iter()
+        .map(|m| {
+            format!(
+                "{:<12} {:>10} {:<7} {:>6.1}% of {:<9} ${:.4}",
+                m.name,
+                m.tokens,
+                if m.exact { "exact" } else { "approx" },
+                m.context_used_percent,
+                m.context_window,
+                m.cost
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn deps(options: &Options) -> Result<String, CliError> {
+    let files = some_paths(options, "deps needs at least one file")?;
+    let mut deps = Vec::new();
+    for file in files {
+        deps.extend(detect_dependencies(file)?);
+    }
+
+    if options.json {
+        return to_json(&deps);
+    }
+
+    Ok(deps
+        .iter()
+        .map(|d| {
+            format!(
+                "{}:{}\t{} ({})",
+                d.file, d.line_number, d.dependency, d.import_type
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn todos(options: &Options) -> Result<String, CliError> {
+    let files = some_paths(options, "todos needs at least one file")?;
+    let mut todos = Vec::new();
+    for file in files {
+        todos.extend(extract_todos(file)?);
+    }
+
+    if options.json {
+        return to_json(&todos);
+    }
+
+    Ok(todos
+        .iter()
+        .map(|t| {
+            format!(
+                "{}:{}\t{}: {}",
+                t.file, t.line_number, t.todo_type, t.message
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn mcp(options: &Options) -> Result<String, CliError> {
+    let roots = if options.positional.is_empty() {
+        vec![".".to_string()]
+    } else {
+        options.positional.clone()
+    };
+
+    let stdin = io::stdin();
+    McpServer::new(&roots)?.serve(stdin.lock(), io::stdout())?;
+    Ok(String::new())
+}
+
+fn single_path<'a>(options: &'a Options, missing: &str) -> Result<&'a str, CliError> {
+    match options.positional.as_slice() {
+        [path] => Ok(path),
+        _ => Err(CliError::Usage(missing.to_string())),
+    }
+}
+
+fn some_paths<'a>(options: &'a Options, missing: &str) -> Result<&'a [String], CliError> {
+    if options.positional.is_empty() {
+        return Err(CliError::Usage(missing.to_string()));
+    }
+    Ok(&options.positional)
+}
+
+/// The configured ignore patterns plus any given on the command line
+fn ignore_patterns(config: &AppConfig, options: &Options) -> Vec<String> {
+    let mut patterns = config.custom_ignore_patterns.clone();
+    patterns.extend(options.ignore.iter().cloned());
+    patterns
+}
+
+fn collect_files(nodes: &[FileNode], files: &mut Vec<String>) {
+    for node in nodes {
+        if node.is_dir {
+            collect_files(node.children.as_deref().unwrap_or_default(), files);
+        } else {
+            files.push(node.path.clone());
+        }
+    }
+}
+
+fn relative_to(path: &str, base: &str) -> String {
+    Path::new(path)
+        .strip_prefix(base)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<String, CliError> {
+    serde_json::to_string_pretty(value)
+        .map_err(|e| AppError::internal(format!("Failed to serialize to JSON: {}", e)).into())
+}
+
+fn write_output(output: &str, path: Option<&str>) -> Result<(), CliError> {
+    match path {
+        Some(path) => {
+            fs::write(path, output).map_err(|e| AppError::io(e, "Failed to write", path).into())
+        }
+        None => {
+            if output.ends_with('\n') || output.is_empty() {
+                print!("{}", output);
+            } else {
+                println!("{}", output);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_scan_and_pack_share_visible_files() {
+        // A fixed config, so the developer's own settings cannot change the result
+        let config = AppConfig::default();
+        let dir = std::env::temp_dir().join(format!("filesurfer_cli_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src").join("main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.join("notes.log"), "debug").unwrap();
+        let root = dir.to_string_lossy().to_string();
+
+        let options = parse_options(&args(&[&root, "--ignore", "*.log"]))
+            .ok()
+            .unwrap();
+        let listed = execute("scan", &options, &config).ok().unwrap();
+        assert_eq!(listed, Path::new("src").join("main.rs").to_string_lossy());
+
+        let options = parse_options(&args(&[&root, "--ignore", "*.log", "--format", "xml"]))
+            .ok()
+            .unwrap();
+        let packed = execute("pack", &options, &config).ok().unwrap();
+        assert!(packed.contains("fn main() {}") && !packed.contains("debug"));
+
+        assert!(matches!(
+            parse_options(&args(&["--max-size"])),
+            Err(CliError::Usage(_))
+        ));
+        assert_eq!(run(&args(&["--some-gui-flag"])), None);
+        let missing = dir.join("missing.rs").to_string_lossy().to_string();
+        let options = parse_options(&args(&[&missing])).ok().unwrap();
+        assert!(matches!(
+            execute("todos", &options, &config),
+            Err(CliError::Failed(error)) if exit_code(error.kind) == 3
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -4,6 +4,10 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
+use crate::error::AppError;
+use crate::import_graph::build_graph;
+use crate::syntax;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Dependency {
     pub file: String,
@@ -22,6 +26,51 @@ pub struct Function {
     pub content: String,
 }
 
+/// A named item in a file's outline. Methods and other members are nested
+/// under the impl, trait, class or module that contains them.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Symbol {
+    pub kind: SymbolKind,
+    pub name: String,
+    /// Name of the enclosing symbol, e.g. the type of an impl block
+    pub parent: Option<String>,
+    /// `None` for impl blocks, which have no visibility of their own
+    pub visibility: Option<Visibility>,
+    pub signature: String,
+    pub line_start: usize,
+    pub line_end: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Symbol>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Struct,
+    Enum,
+    Trait,
+    Impl,
+    Class,
+    Interface,
+    TypeAlias,
+    Module,
+    Constant,
+}
+
+/// Visibility as written in the source: `pub(crate)` and friends are
+/// `Restricted`, non-exported JS/TS items and `_`-prefixed Python names are
+/// `Private`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    Public,
+    Restricted,
+    Protected,
+    Private,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TodoItem {
     pub file: String,
@@ -39,9 +88,9 @@ pub struct ProjectType {
 }
 
 /// Detect dependencies in a file
-pub fn detect_dependencies(file_path: &str) -> Result<Vec<Dependency>, String> {
+pub fn detect_dependencies(file_path: &str) -> Result<Vec<Dependency>, AppError> {
     let content =
-        fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+        fs::read_to_string(file_path).map_err(|e| AppError::io(e, "Failed to read", file_path))?;
 
     let mut dependencies = Vec::new();
     let extension = Path::new(file_path)
@@ -49,6 +98,11 @@ pub fn detect_dependencies(file_path: &str) -> Result<Vec<Dependency>, String> {
         .and_then(|e| e.to_str())
         .unwrap_or("");
 
+    if let Some(parsed) = syntax::dependencies(file_path, &content, extension) {
+        return Ok(parsed);
+    }
+
+    // Regex fallback for languages without a grammar
     match extension {
         "js" | "jsx" | "ts" | "tsx" | "mjs" => {
             dependencies.extend(detect_js_dependencies(file_path, &content)?);
@@ -68,7 +122,7 @@ pub fn detect_dependencies(file_path: &str) -> Result<Vec<Dependency>, String> {
     Ok(dependencies)
 }
 
-fn detect_js_dependencies(file: &str, content: &str) -> Result<Vec<Dependency>, String> {
+fn detect_js_dependencies(file: &str, content: &str) -> Result<Vec<Dependency>, AppError> {
     let mut deps = Vec::new();
 
     // Match: import ... from "..."
@@ -98,7 +152,7 @@ fn detect_js_dependencies(file: &str, content: &str) -> Result<Vec<Dependency>,
     Ok(deps)
 }
 
-fn detect_python_dependencies(file: &str, content: &str) -> Result<Vec<Dependency>, String> {
+fn detect_python_dependencies(file: &str, content: &str) -> Result<Vec<Dependency>, AppError> {
     let mut deps = Vec::new();
 
     let import_re = Regex::new(r"^import\s+(.+)$").unwrap();
@@ -127,7 +181,7 @@ fn detect_python_dependencies(file: &str, content: &str) -> Result<Vec<Dependenc
     Ok(deps)
 }
 
-fn detect_rust_dependencies(file: &str, content: &str) -> Result<Vec<Dependency>, String> {
+fn detect_rust_dependencies(file: &str, content: &str) -> Result<Vec<Dependency>, AppError> {
     let mut deps = Vec::new();
 
     let use_re = Regex::new(r"^use\s+([^;]+);").unwrap();
@@ -147,7 +201,7 @@ fn detect_rust_dependencies(file: &str, content: &str) -> Result<Vec<Dependency>
     Ok(deps)
 }
 
-fn detect_go_dependencies(file: &str, content: &str) -> Result<Vec<Dependency>, String> {
+fn detect_go_dependencies(file: &str, content: &str) -> Result<Vec<Dependency>, AppError> {
     let mut deps = Vec::new();
 
     let import_re = Regex::new(r#"import\s+"([^"]+)""#).unwrap();
@@ -167,24 +221,46 @@ fn detect_go_dependencies(file: &str, content: &str) -> Result<Vec<Dependency>,
 }
 
 /// Extract functions from a file
-pub fn extract_functions(file_path: &str) -> Result<Vec<Function>, String> {
+pub fn extract_functions(file_path: &str) -> Result<Vec<Function>, AppError> {
     let content =
-        fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+        fs::read_to_string(file_path).map_err(|e| AppError::io(e, "Failed to read", file_path))?;
 
+    extract_functions_from(file_path, &content)
+}
+
+/// Extract functions from content that was already read
+pub fn extract_functions_from(file_path: &str, content: &str) -> Result<Vec<Function>, AppError> {
     let extension = Path::new(file_path)
         .extension()
         .and_then(|e| e.to_str())
         .unwrap_or("");
 
+    if let Some(parsed) = syntax::functions(file_path, content, extension) {
+        return Ok(parsed);
+    }
+
+    // Regex fallback for languages without a grammar
     match extension {
-        "js" | "jsx" | "ts" | "tsx" => extract_js_functions(file_path, &content),
-        "py" => extract_python_functions(file_path, &content),
-        "rs" => extract_rust_functions(file_path, &content),
+        "js" | "jsx" | "ts" | "tsx" => extract_js_functions(file_path, content),
+        "py" => extract_python_functions(file_path, content),
+        "rs" => extract_rust_functions(file_path, content),
         _ => Ok(Vec::new()),
     }
 }
 
-fn extract_js_functions(file: &str, content: &str) -> Result<Vec<Function>, String> {
+/// Extract the symbol outline of a file. Empty for languages without a grammar.
+pub fn extract_outline(file_path: &str) -> Result<Vec<Symbol>, AppError> {
+    let content =
+        fs::read_to_string(file_path).map_err(|e| AppError::io(e, "Failed to read", file_path))?;
+    let extension = Path::new(file_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+
+    Ok(syntax::outline(&content, extension).unwrap_or_default())
+}
+
+fn extract_js_functions(file: &str, content: &str) -> Result<Vec<Function>, AppError> {
     let mut functions = Vec::new();
 
     // Match: function name(...) { }, const name = (...) => { }, etc.
@@ -205,9 +281,9 @@ fn extract_js_functions(file: &str, content: &str) -> Result<Vec<Function>, Stri
                 line.matches('{').count() as i32 - line.matches('}').count() as i32;
             let mut end_line = line_num;
 
-            for i in (line_num + 1)..lines.len() {
-                brace_count += lines[i].matches('{').count() as i32;
-                brace_count -= lines[i].matches('}').count() as i32;
+            for (i, next) in lines.iter().enumerate().skip(line_num + 1) {
+                brace_count += next.matches('{').count() as i32;
+                brace_count -= next.matches('}').count() as i32;
 
                 if brace_count == 0 {
                     end_line = i;
@@ -235,7 +311,7 @@ fn extract_js_functions(file: &str, content: &str) -> Result<Vec<Function>, Stri
     Ok(functions)
 }
 
-fn extract_python_functions(file: &str, content: &str) -> Result<Vec<Function>, String> {
+fn extract_python_functions(file: &str, content: &str) -> Result<Vec<Function>, AppError> {
     let mut functions = Vec::new();
 
     let func_re = Regex::new(r"^def\s+(\w+)\s*\([^)]*\):").unwrap();
@@ -250,9 +326,9 @@ fn extract_python_functions(file: &str, content: &str) -> Result<Vec<Function>,
             let base_indent = line.len() - line.trim_start().len();
             let mut end_line = line_num;
 
-            for i in (line_num + 1)..lines.len() {
-                let current_indent = lines[i].len() - lines[i].trim_start().len();
-                if !lines[i].trim().is_empty() && current_indent <= base_indent {
+            for (i, next) in lines.iter().enumerate().skip(line_num + 1) {
+                let current_indent = next.len() - next.trim_start().len();
+                if !next.trim().is_empty() && current_indent <= base_indent {
                     end_line = i - 1;
                     break;
                 }
@@ -275,7 +351,7 @@ fn extract_python_functions(file: &str, content: &str) -> Result<Vec<Function>,
     Ok(functions)
 }
 
-fn extract_rust_functions(file: &str, content: &str) -> Result<Vec<Function>, String> {
+fn extract_rust_functions(file: &str, content: &str) -> Result<Vec<Function>, AppError> {
     let mut functions = Vec::new();
 
     let func_re = Regex::new(r"fn\s+(\w+)\s*\([^)]*\)").unwrap();
@@ -291,9 +367,9 @@ fn extract_rust_functions(file: &str, content: &str) -> Result<Vec<Function>, St
                 line.matches('{').count() as i32 - line.matches('}').count() as i32;
             let mut end_line = line_num;
 
-            for i in (line_num + 1)..lines.len() {
-                brace_count += lines[i].matches('{').count() as i32;
-                brace_count -= lines[i].matches('}').count() as i32;
+            for (i, next) in lines.iter().enumerate().skip(line_num + 1) {
+                brace_count += next.matches('{').count() as i32;
+                brace_count -= next.matches('}').count() as i32;
 
                 if brace_count == 0 {
                     end_line = i;
@@ -322,8 +398,9 @@ fn extract_rust_functions(file: &str, content: &str) -> Result<Vec<Function>, St
 }
 
 /// Extract TODO/FIXME/NOTE comments
-pub fn extract_todos(file_path: &str) -> Result<Vec<TodoItem>, String> {
-    let content = fs::read_to_string(file_path).map_err(|e| format!("Failed to read: {}", e))?;
+pub fn extract_todos(file_path: &str) -> Result<Vec<TodoItem>, AppError> {
+    let content =
+        fs::read_to_string(file_path).map_err(|e| AppError::io(e, "Failed to read", file_path))?;
 
     let mut todos = Vec::new();
     let todo_re = Regex::new(r"(?://|#|/\*)\s*(TODO|FIXME|NOTE|HACK|XXX):?\s*(.*)").unwrap();
@@ -349,6 +426,10 @@ pub fn filter_comments(content: &str, extension: &str, include: bool) -> String
         return content.to_string();
     }
 
+    if let Some(stripped) = syntax::strip_comments(content, extension) {
+        return stripped;
+    }
+
     match extension {
         "js" | "jsx" | "ts" | "tsx" | "rs" | "go" | "java" | "c" | "cpp" => {
             filter_c_style_comments(content)
@@ -382,7 +463,7 @@ fn filter_hash_comments(content: &str) -> String {
 }
 
 /// Detect project type from directory
-pub fn detect_project_type(dir_path: &str) -> Result<ProjectType, String> {
+pub fn detect_project_type(dir_path: &str) -> Result<ProjectType, AppError> {
     let indicators = vec![
         ("package.json", "Node.js"),
         ("next.config.js", "Next.js"),
@@ -415,7 +496,7 @@ pub fn detect_project_type(dir_path: &str) -> Result<ProjectType, String> {
         && (found_indicators.contains(&"next.config.js".to_string())
             || found_indicators.contains(&"next.config.ts".to_string()))
     {
-        *scores.entry(&"Next.js").or_insert(0.0) += 2.0;
+        *scores.entry("Next.js").or_insert(0.0) += 2.0;
     }
 
     let (detected_type, confidence) = scores
@@ -431,56 +512,14 @@ pub fn detect_project_type(dir_path: &str) -> Result<ProjectType, String> {
     })
 }
 
-/// Resolve imports to absolute paths
-pub fn resolve_imports(file_path: &str) -> Result<Vec<String>, String> {
-    let dependencies = detect_dependencies(file_path)?;
-    let base_dir = Path::new(file_path)
-        .parent()
-        .ok_or("Could not get parent directory")?;
-
-    let mut resolved_paths = Vec::new();
-
-    for dep in dependencies {
-        // Skip system/library imports (heuristic: no ./ or ../ and no extension)
-        // This is a basic heuristic and might need refinement
-        if !dep.dependency.starts_with('.') && !dep.dependency.starts_with('/') {
-            continue;
-        }
-
-        let dep_path = dep.dependency;
-        let potential_path = base_dir.join(&dep_path);
-
-        // Try exact match
-        if potential_path.exists() && potential_path.is_file() {
-            if let Some(s) = potential_path.to_str() {
-                resolved_paths.push(s.to_string());
-            }
-            continue;
-        }
-
-        // Try adding extensions
-        let extensions = ["js", "jsx", "ts", "tsx", "css", "scss", "json", "py", "rs"];
-        for ext in extensions {
-            let with_ext = potential_path.with_extension(ext);
-            if with_ext.exists() && with_ext.is_file() {
-                if let Some(s) = with_ext.to_str() {
-                    resolved_paths.push(s.to_string());
-                }
-                break;
-            }
-
-            // Handle index files (e.g. import from "./components" -> "./components/index.js")
-            let index_path = potential_path.join(format!("index.{}", ext));
-            if index_path.exists() && index_path.is_file() {
-                if let Some(s) = index_path.to_str() {
-                    resolved_paths.push(s.to_string());
-                }
-                break;
-            }
-        }
+/// Resolve the local files a file imports directly to absolute paths
+pub fn resolve_imports(file_path: &str) -> Result<Vec<String>, AppError> {
+    let mut graph = build_graph(&[file_path.to_string()], 1, |_| true);
+    if let Some(error) = graph.nodes.first_mut().and_then(|seed| seed.error.take()) {
+        return Err(error);
     }
 
-    // Remove duplicates
+    let mut resolved_paths: Vec<String> = graph.edges.into_iter().map(|edge| edge.to).collect();
     resolved_paths.sort();
     resolved_paths.dedup();
 
@@ -3,6 +3,11 @@ use std::collections::VecDeque;
 use std::fs;
 use std::path::PathBuf;
 
+use crate::error::AppError;
+use crate::git::{DiffSettings, GitMetadata};
+use crate::project_tree::TreeOptions;
+use crate::secrets::SecretScanConfig;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AppConfig {
     pub theme: String,
@@ -13,6 +18,37 @@ pub struct AppConfig {
     pub git_only_mode: bool,
     pub include_comments: bool,
     pub show_token_count: bool,
+    #[serde(default)]
+    pub lazy_loading: bool,
+    #[serde(default)]
+    pub http_api_enabled: bool,
+    #[serde(default = "default_http_api_port")]
+    pub http_api_port: u16,
+    /// Generated the first time the API starts
+    #[serde(default)]
+    pub http_api_token: String,
+    /// Files and directories outside the workspace that commands may still access
+    #[serde(default)]
+    pub allowed_paths: Vec<String>,
+    /// Credential detection applied to everything that is copied or exported
+    #[serde(default)]
+    pub secret_scan: SecretScanConfig,
+    /// Base ref and context lines for the diff output format
+    #[serde(default)]
+    pub diff: DiffSettings,
+    /// Git history added to each copied file
+    #[serde(default)]
+    pub git_metadata: GitMetadata,
+    /// Tree of the project put ahead of copied files; off when absent
+    #[serde(default)]
+    pub project_tree: Option<TreeOptions>,
+    /// Copy unfocused files as signatures only
+    #[serde(default)]
+    pub skeleton: bool,
+}
+
+fn default_http_api_port() -> u16 {
+    7615
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -40,6 +76,29 @@ pub struct ClipboardHistory {
     pub max_items: usize,
 }
 
+/// One entry of the model registry in `models.json`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelConfig {
+    pub id: String,
+    pub name: String,
+    /// Bundled BPE encoding used for counting ("cl100k_base" or "o200k_base")
+    #[serde(default)]
+    pub tokenizer: Option<String>,
+    /// Multiplier applied to the tokenizer's count when it only approximates the model
+    #[serde(default)]
+    pub scale: Option<f32>,
+    /// Estimation ratio for models without a known tokenizer
+    #[serde(default)]
+    pub chars_per_token: Option<f32>,
+    pub context_window: u32,
+    pub input_price_per_million: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelRegistry {
+    pub models: Vec<ModelConfig>,
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
@@ -51,10 +110,61 @@ impl Default for AppConfig {
             git_only_mode: false,
             include_comments: true,
             show_token_count: true,
+            lazy_loading: false,
+            http_api_enabled: false,
+            http_api_port: default_http_api_port(),
+            http_api_token: String::new(),
+            allowed_paths: Vec::new(),
+            secret_scan: SecretScanConfig::default(),
+            diff: DiffSettings::default(),
+            git_metadata: GitMetadata::Off,
+            project_tree: None,
+            skeleton: false,
         }
     }
 }
 
+impl Default for ModelRegistry {
+    fn default() -> Self {
+        Self {
+            models: vec![
+                bpe_model("gpt4", "GPT-4", "cl100k_base", None, 128_000, 10.0),
+                bpe_model("gpt4o", "GPT-4o", "o200k_base", None, 128_000, 2.5),
+                // Claude uses slightly more tokens for the same content
+                bpe_model("claude", "Claude", "cl100k_base", Some(1.05), 200_000, 3.0),
+                // Gemini is ~10% more efficient than GPT-4 on average for code
+                bpe_model(
+                    "gemini",
+                    "Gemini",
+                    "cl100k_base",
+                    Some(0.9),
+                    1_000_000,
+                    1.25,
+                ),
+            ],
+        }
+    }
+}
+
+fn bpe_model(
+    id: &str,
+    name: &str,
+    tokenizer: &str,
+    scale: Option<f32>,
+    context_window: u32,
+    price: f64,
+) -> ModelConfig {
+    ModelConfig {
+        id: id.to_string(),
+        name: name.to_string(),
+        tokenizer: Some(tokenizer.to_string()),
+        scale,
+        chars_per_token: None,
+        context_window,
+        input_price_per_million: price,
+    }
+}
+
 impl Default for WorkspaceState {
     fn default() -> Self {
         Self {
@@ -81,60 +191,106 @@ impl Default for ClipboardHistory {
     }
 }
 
-fn get_config_dir() -> Result<PathBuf, String> {
+fn get_config_dir() -> Result<PathBuf, AppError> {
     let config_dir =
-        dirs::config_dir().ok_or_else(|| "Could not find config directory".to_string())?;
+        dirs::config_dir().ok_or_else(|| AppError::not_found("Could not find config directory"))?;
 
     let app_config_dir = config_dir.join("FileSurfer");
     fs::create_dir_all(&app_config_dir)
-        .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        .map_err(|e| AppError::io(e, "Failed to create config directory", &app_config_dir))?;
 
     Ok(app_config_dir)
 }
 
-fn get_config_path() -> Result<PathBuf, String> {
+fn get_config_path() -> Result<PathBuf, AppError> {
     Ok(get_config_dir()?.join("config.json"))
 }
 
-fn get_workspace_path(workspace_path: &str) -> Result<PathBuf, String> {
+fn get_models_path() -> Result<PathBuf, AppError> {
+    Ok(get_config_dir()?.join("models.json"))
+}
+
+fn get_workspace_path(workspace_path: &str) -> Result<PathBuf, AppError> {
     let config_dir = get_config_dir()?;
     // Create safe filename from path
     let safe_name = workspace_path.replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_");
     Ok(config_dir.join(format!("workspace_{}.json", safe_name)))
 }
 
-fn get_clipboard_history_path() -> Result<PathBuf, String> {
+fn get_clipboard_history_path() -> Result<PathBuf, AppError> {
     Ok(get_config_dir()?.join("clipboard_history.json"))
 }
 
 // App Config Functions
-pub fn save_config(config: &AppConfig) -> Result<(), String> {
+pub fn save_config(config: &AppConfig) -> Result<(), AppError> {
     let config_path = get_config_path()?;
     let json = serde_json::to_string_pretty(config)
-        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        .map_err(|e| AppError::internal(format!("Failed to serialize config: {}", e)))?;
 
-    fs::write(config_path, json).map_err(|e| format!("Failed to write config: {}", e))?;
+    fs::write(&config_path, json)
+        .map_err(|e| AppError::io(e, "Failed to write config", &config_path))?;
 
     Ok(())
 }
 
-pub fn load_config() -> Result<AppConfig, String> {
+pub fn load_config() -> Result<AppConfig, AppError> {
     let config_path = get_config_path()?;
 
     if !config_path.exists() {
         return Ok(AppConfig::default());
     }
 
-    let json =
-        fs::read_to_string(config_path).map_err(|e| format!("Failed to read config: {}", e))?;
+    let json = fs::read_to_string(&config_path)
+        .map_err(|e| AppError::io(e, "Failed to read config", &config_path))?;
 
-    let config: AppConfig =
-        serde_json::from_str(&json).map_err(|e| format!("Failed to parse config: {}", e))?;
+    let config: AppConfig = serde_json::from_str(&json).map_err(|e| {
+        AppError::invalid(format!("Failed to parse config: {}", e)).with_path(&config_path)
+    })?;
 
     Ok(config)
 }
 
-pub fn add_recent_path(path: String) -> Result<(), String> {
+// Model Registry Functions
+pub fn save_model_registry(registry: &ModelRegistry) -> Result<(), AppError> {
+    let models_path = get_models_path()?;
+    let json = serde_json::to_string_pretty(registry)
+        .map_err(|e| AppError::internal(format!("Failed to serialize models: {}", e)))?;
+
+    fs::write(&models_path, json)
+        .map_err(|e| AppError::io(e, "Failed to write models", &models_path))?;
+
+    Ok(())
+}
+
+/// Load `models.json`, writing the built-in registry on first use so it can be edited
+pub fn load_model_registry() -> Result<ModelRegistry, AppError> {
+    let models_path = get_models_path()?;
+
+    if !models_path.exists() {
+        let registry = ModelRegistry::default();
+        save_model_registry(&registry)?;
+        return Ok(registry);
+    }
+
+    let json = fs::read_to_string(&models_path)
+        .map_err(|e| AppError::io(e, "Failed to read models", &models_path))?;
+
+    let registry: ModelRegistry = serde_json::from_str(&json).map_err(|e| {
+        AppError::invalid(format!("Failed to parse models: {}", e)).with_path(&models_path)
+    })?;
+
+    Ok(registry)
+}
+
+pub fn find_model<'a>(registry: &'a ModelRegistry, id: &str) -> Result<&'a ModelConfig, AppError> {
+    registry
+        .models
+        .iter()
+        .find(|model| model.id == id)
+        .ok_or_else(|| AppError::not_found(format!("Unknown model: {}", id)))
+}
+
+pub fn add_recent_path(path: String) -> Result<(), AppError> {
     let mut config = load_config()?;
 
     // Remove if already exists
@@ -152,17 +308,18 @@ pub fn add_recent_path(path: String) -> Result<(), String> {
 }
 
 // Workspace State Functions
-pub fn save_workspace_state(state: &WorkspaceState) -> Result<(), String> {
+pub fn save_workspace_state(state: &WorkspaceState) -> Result<(), AppError> {
     let workspace_path = get_workspace_path(&state.path)?;
     let json = serde_json::to_string_pretty(state)
-        .map_err(|e| format!("Failed to serialize workspace: {}", e))?;
+        .map_err(|e| AppError::internal(format!("Failed to serialize workspace: {}", e)))?;
 
-    fs::write(workspace_path, json).map_err(|e| format!("Failed to write workspace: {}", e))?;
+    fs::write(&workspace_path, json)
+        .map_err(|e| AppError::io(e, "Failed to write workspace", &workspace_path))?;
 
     Ok(())
 }
 
-pub fn load_workspace_state(path: &str) -> Result<WorkspaceState, String> {
+pub fn load_workspace_state(path: &str) -> Result<WorkspaceState, AppError> {
     let workspace_path = get_workspace_path(path)?;
 
     if !workspace_path.exists() {
@@ -171,11 +328,12 @@ pub fn load_workspace_state(path: &str) -> Result<WorkspaceState, String> {
         return Ok(default_state);
     }
 
-    let json = fs::read_to_string(workspace_path)
-        .map_err(|e| format!("Failed to read workspace: {}", e))?;
+    let json = fs::read_to_string(&workspace_path)
+        .map_err(|e| AppError::io(e, "Failed to read workspace", &workspace_path))?;
 
-    let state: WorkspaceState =
-        serde_json::from_str(&json).map_err(|e| format!("Failed to parse workspace: {}", e))?;
+    let state: WorkspaceState = serde_json::from_str(&json).map_err(|e| {
+        AppError::invalid(format!("Failed to parse workspace: {}", e)).with_path(&workspace_path)
+    })?;
 
     Ok(state)
 }
@@ -185,7 +343,7 @@ pub fn add_to_clipboard_history(
     content: String,
     file_count: usize,
     format: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let mut history = load_clipboard_history()?;
 
     let timestamp = std::time::SystemTime::now()
@@ -211,34 +369,36 @@ pub fn add_to_clipboard_history(
     save_clipboard_history(&history)
 }
 
-pub fn load_clipboard_history() -> Result<ClipboardHistory, String> {
+pub fn load_clipboard_history() -> Result<ClipboardHistory, AppError> {
     let history_path = get_clipboard_history_path()?;
 
     if !history_path.exists() {
         return Ok(ClipboardHistory::default());
     }
 
-    let json = fs::read_to_string(history_path)
-        .map_err(|e| format!("Failed to read clipboard history: {}", e))?;
+    let json = fs::read_to_string(&history_path)
+        .map_err(|e| AppError::io(e, "Failed to read clipboard history", &history_path))?;
 
-    let history: ClipboardHistory = serde_json::from_str(&json)
-        .map_err(|e| format!("Failed to parse clipboard history: {}", e))?;
+    let history: ClipboardHistory = serde_json::from_str(&json).map_err(|e| {
+        AppError::invalid(format!("Failed to parse clipboard history: {}", e))
+            .with_path(&history_path)
+    })?;
 
     Ok(history)
 }
 
-pub fn save_clipboard_history(history: &ClipboardHistory) -> Result<(), String> {
+pub fn save_clipboard_history(history: &ClipboardHistory) -> Result<(), AppError> {
     let history_path = get_clipboard_history_path()?;
     let json = serde_json::to_string_pretty(history)
-        .map_err(|e| format!("Failed to serialize clipboard history: {}", e))?;
+        .map_err(|e| AppError::internal(format!("Failed to serialize clipboard history: {}", e)))?;
 
-    fs::write(history_path, json)
-        .map_err(|e| format!("Failed to write clipboard history: {}", e))?;
+    fs::write(&history_path, json)
+        .map_err(|e| AppError::io(e, "Failed to write clipboard history", &history_path))?;
 
     Ok(())
 }
 
-pub fn clear_clipboard_history() -> Result<(), String> {
+pub fn clear_clipboard_history() -> Result<(), AppError> {
     let history = ClipboardHistory::default();
     save_clipboard_history(&history)
 }
@@ -0,0 +1,135 @@
+use serde::Serialize;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use crate::path_policy::PathError;
+
+/// What went wrong, so the UI can offer a fix and the CLI can pick an exit code
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    NotFound,
+    PermissionDenied,
+    NotAGitRepo,
+    OutsideWorkspace,
+    InvalidInput,
+    Cancelled,
+    /// Block mode found credentials and the copy was not confirmed
+    SecretsFound,
+    Io,
+    Internal,
+}
+
+/// The error every command returns, serialized to the frontend as an object
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct AppError {
+    pub kind: ErrorKind,
+    pub path: Option<String>,
+    /// The underlying `std::io::ErrorKind`, e.g. `NotFound`, when there was one
+    pub io_kind: Option<String>,
+    pub message: String,
+}
+
+impl AppError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            path: None,
+            io_kind: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn with_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path = Some(path.as_ref().to_string_lossy().to_string());
+        self
+    }
+
+    /// Wrap an io error, e.g. `AppError::io(e, "Failed to read", path)`
+    pub fn io(error: io::Error, action: &str, path: impl AsRef<Path>) -> Self {
+        let kind = match error.kind() {
+            io::ErrorKind::NotFound => ErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData => ErrorKind::InvalidInput,
+            _ => ErrorKind::Io,
+        };
+        let path = path.as_ref();
+
+        Self {
+            kind,
+            path: Some(path.to_string_lossy().to_string()),
+            io_kind: Some(format!("{:?}", error.kind())),
+            message: format!("{} {}: {}", action, path.display(), error),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidInput, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Internal, message)
+    }
+
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Cancelled, message)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<PathError> for AppError {
+    fn from(error: PathError) -> Self {
+        let (kind, path) = match &error {
+            PathError::NoWorkspace { path }
+            | PathError::OutsideWorkspace { path }
+            | PathError::OutsideBasePath { path, .. } => (ErrorKind::OutsideWorkspace, path),
+            PathError::Unresolvable { path, .. } | PathError::TooBroad { path } => {
+                (ErrorKind::InvalidInput, path)
+            }
+        };
+        AppError::new(kind, error.to_string()).with_path(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_keep_kind_and_path() {
+        let missing = std::env::temp_dir().join("filesurfer_error_missing.txt");
+        let error = AppError::io(
+            std::fs::read_to_string(&missing).unwrap_err(),
+            "Failed to read",
+            &missing,
+        );
+        assert_eq!(error.kind, ErrorKind::NotFound);
+        assert_eq!(error.io_kind.as_deref(), Some("NotFound"));
+
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["kind"], "not_found");
+        assert_eq!(value["path"], missing.to_string_lossy().as_ref());
+        assert!(value["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Failed to read"));
+
+        let refused = AppError::from(PathError::OutsideWorkspace {
+            path: "/etc/hosts".to_string(),
+        });
+        assert_eq!(refused.kind, ErrorKind::OutsideWorkspace);
+        assert_eq!(refused.path.as_deref(), Some("/etc/hosts"));
+    }
+}
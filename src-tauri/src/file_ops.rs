@@ -1,12 +1,25 @@
-use git2::Repository;
 use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::Writer;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::borrow::Cow;
 use std::fs;
 use std::io::Cursor;
 use std::path::Path;
 use walkdir::WalkDir;
 
+use crate::error::{AppError, ErrorKind};
+use crate::git::{
+    annotate_loaded, diff_loaded, open_repository, DiffSettings, FileHistory, GitMetadata,
+};
+use crate::ignore_rules::{IgnoreMatch, IgnoreRules};
+use crate::jobs::JobContext;
+use crate::project_tree::{render_tree, TreeOptions};
+use crate::read_pipeline::{load_files, FileBody, LoadedFile};
+use crate::secrets::{ensure_confirmed, scan_loaded, SecretFinding, SecretMode, SecretScanConfig};
+use crate::skeleton::{skeleton_loaded, SkeletonOptions};
+use crate::slices::{slice_loaded, Selection};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FileNode {
     pub name: String,
@@ -14,14 +27,51 @@ pub struct FileNode {
     pub is_dir: bool,
     pub size: u64,
     pub children: Option<Vec<FileNode>>,
+    #[serde(default)]
+    pub has_children: bool,
+    #[serde(default)]
+    pub child_count: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct CopyRequest {
     pub files: Vec<String>,
     pub base_path: String,
     pub format: String,
     pub max_file_size_mb: Option<u64>,
+    #[serde(default)]
+    pub ignore_patterns: Vec<String>,
+    /// Secret scanning for this read; commands fill in the configured one when absent
+    #[serde(default)]
+    pub secret_scan: Option<SecretScanConfig>,
+    /// Copy even though block mode found secrets
+    #[serde(default)]
+    pub secrets_confirmed: bool,
+    /// Used by the `diff` format; compares with HEAD when absent
+    #[serde(default)]
+    pub diff: Option<DiffSettings>,
+    /// Last commit, and optionally blame, shown with each file
+    #[serde(default)]
+    pub git_metadata: GitMetadata,
+    /// Put a tree of the project ahead of the files
+    #[serde(default)]
+    pub tree: Option<TreeOptions>,
+    /// Show files outside the focus as signatures only
+    #[serde(default)]
+    pub skeleton: Option<SkeletonOptions>,
+    /// Symbols or line ranges to copy instead of whole files. A file with
+    /// selections is copied as just those parts, listed in `files` or not.
+    #[serde(default)]
+    pub selections: Vec<Selection>,
+}
+
+/// Formatted files along with the secrets found in them
+#[derive(Debug, Serialize, Clone)]
+pub struct ReadOutput {
+    pub content: String,
+    pub findings: Vec<SecretFinding>,
+    /// The scan mode that was applied, so the UI can tell redacted from reported
+    pub secret_mode: SecretMode,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -30,124 +80,216 @@ pub struct ExportRequest {
     pub path: String,
 }
 
-// Default ignore patterns
-const IGNORE_PATTERNS: &[&str] = &[
-    "node_modules",
-    ".git",
-    "dist",
-    "build",
-    "target",
-    ".next",
-    "out",
-    "coverage",
-    ".cache",
-    ".vscode",
-    ".idea",
-    "__pycache__",
-    "*.pyc",
-    ".DS_Store",
-    "Thumbs.db",
-];
-
-pub fn should_ignore(path: &Path, custom_patterns: &[String]) -> bool {
-    let path_str = path.to_string_lossy();
-
-    // Check default patterns
-    for pattern in IGNORE_PATTERNS {
-        if pattern.contains('*') {
-            let pattern_clean = pattern.trim_start_matches("*.");
-            if let Some(ext) = path.extension() {
-                if ext == pattern_clean {
-                    return true;
-                }
-            }
-        } else {
-            if path_str.contains(pattern) {
-                return true;
-            }
-        }
+/// Explain why `path` is hidden from the tree of `root`, if it is
+pub fn explain_ignored(
+    root: &str,
+    path: &str,
+    custom_patterns: &[String],
+) -> Result<Option<IgnoreMatch>, AppError> {
+    let root = Path::new(root);
+    let path = Path::new(path);
+
+    if !path.exists() {
+        return Err(AppError::not_found("Path does not exist").with_path(path));
     }
 
-    // Check custom patterns
-    for pattern in custom_patterns {
-        if pattern.contains('*') {
-            let pattern_clean = pattern.trim_start_matches("*.");
-            if let Some(ext) = path.extension() {
-                if ext == pattern_clean {
-                    return true;
-                }
-            }
-        } else {
-            if path_str.contains(pattern) {
-                return true;
-            }
-        }
+    if !path.starts_with(root) {
+        return Err(AppError::new(
+            ErrorKind::OutsideWorkspace,
+            "Path is not inside the scanned directory",
+        )
+        .with_path(path));
     }
 
-    false
+    let rules = IgnoreRules::new(root, custom_patterns)?;
+    Ok(rules.matched_path(path, path.is_dir()))
 }
 
-fn is_binary_file(path: &Path) -> bool {
-    if let Ok(content) = fs::read(path) {
-        let check_len = content.len().min(8000);
-        for &byte in &content[..check_len] {
-            if byte == 0 {
-                return true;
-            }
-        }
-    }
-    false
+pub fn scan_directory(
+    dir_path: &str,
+    custom_patterns: Vec<String>,
+) -> Result<Vec<FileNode>, AppError> {
+    scan_directory_with(dir_path, custom_patterns, &JobContext::detached())
 }
 
-pub fn scan_directory(
+/// Scan a directory as part of a background job, reporting every visited entry
+pub fn scan_directory_with(
     dir_path: &str,
     custom_patterns: Vec<String>,
-) -> Result<Vec<FileNode>, String> {
+    job: &JobContext,
+) -> Result<Vec<FileNode>, AppError> {
     let root = Path::new(dir_path);
 
     if !root.exists() {
-        return Err("Directory does not exist".to_string());
+        return Err(AppError::not_found("Directory does not exist").with_path(root));
     }
 
-    let mut nodes = Vec::new();
+    let rules = IgnoreRules::new(root, &custom_patterns)?;
+    let nodes = scan_level(root, &rules, None, job);
+
+    if job.is_cancelled() {
+        return Err(AppError::cancelled("Scan cancelled"));
+    }
+
+    Ok(nodes)
+}
+
+/// List a single level of `dir_path`, a directory inside the workspace `root`.
+///
+/// Subdirectories come back with `children: None` and their visible entry
+/// count, so the tree can load them on demand as they are expanded.
+pub fn list_directory(
+    root: &str,
+    dir_path: &str,
+    custom_patterns: &[String],
+) -> Result<Vec<FileNode>, AppError> {
+    let root = Path::new(root);
+    let dir = Path::new(dir_path);
+
+    if !dir.is_dir() {
+        return Err(AppError::not_found("Directory does not exist").with_path(dir));
+    }
+
+    if !dir.starts_with(root) {
+        return Err(AppError::new(
+            ErrorKind::OutsideWorkspace,
+            "Directory is not inside the scanned directory",
+        )
+        .with_path(dir));
+    }
+
+    let rules = IgnoreRules::new(root, custom_patterns)?;
+    if dir != root && rules.matched_path(dir, true).is_some() {
+        return Err(AppError::invalid("Directory is ignored").with_path(dir));
+    }
+
+    Ok(scan_level(
+        dir,
+        &rules.descend(dir),
+        Some(0),
+        &JobContext::detached(),
+    ))
+}
+
+/// Scan `dir_path` loading at most `max_depth` levels, e.g. for a tree that
+/// collapses deeper directories; those come back like in `list_directory`
+pub fn scan_directory_to_depth(
+    dir_path: &str,
+    custom_patterns: &[String],
+    max_depth: usize,
+) -> Result<Vec<FileNode>, AppError> {
+    let root = Path::new(dir_path);
+
+    if !root.exists() {
+        return Err(AppError::not_found("Directory does not exist").with_path(root));
+    }
 
-    for entry in WalkDir::new(root)
+    let rules = IgnoreRules::new(root, custom_patterns)?;
+    Ok(scan_level(
+        root,
+        &rules,
+        Some(max_depth.saturating_sub(1)),
+        &JobContext::detached(),
+    ))
+}
+
+fn visible_entries<'a>(
+    dir: &Path,
+    rules: &'a IgnoreRules,
+) -> impl Iterator<Item = walkdir::DirEntry> + 'a {
+    WalkDir::new(dir)
         .max_depth(1)
         .min_depth(1)
         .into_iter()
-        .filter_entry(|e| !should_ignore(e.path(), &custom_patterns))
-    {
-        match entry {
-            Ok(entry) => {
-                let path = entry.path();
-                let metadata = match entry.metadata() {
-                    Ok(m) => m,
-                    Err(_) => continue,
-                };
-
-                let node = FileNode {
-                    name: entry.file_name().to_string_lossy().to_string(),
-                    path: path.to_string_lossy().to_string(),
-                    is_dir: metadata.is_dir(),
-                    size: if metadata.is_file() {
-                        metadata.len()
-                    } else {
-                        0
-                    },
-                    children: if metadata.is_dir() {
-                        match scan_directory(&path.to_string_lossy(), custom_patterns.clone()) {
-                            Ok(children) => Some(children),
-                            Err(_) => Some(Vec::new()),
-                        }
-                    } else {
-                        None
-                    },
-                };
-
-                nodes.push(node);
-            }
-            Err(_) => continue,
+        .filter_entry(|e| rules.matched(e.path(), e.file_type().is_dir()).is_none())
+        .filter_map(|e| e.ok())
+}
+
+/// Build the node for a single visible path, given the rules of its parent directory.
+///
+/// `depth` is how many levels below `path` to load, or `None` for all of them.
+fn build_node(
+    path: &Path,
+    metadata: &fs::Metadata,
+    rules: &IgnoreRules,
+    depth: Option<usize>,
+    job: &JobContext,
+) -> FileNode {
+    let (children, child_count) = if !metadata.is_dir() {
+        (None, 0)
+    } else if depth != Some(0) {
+        let children = scan_level(path, &rules.enter(path), depth.map(|depth| depth - 1), job);
+        let count = children.len();
+        (Some(children), count)
+    } else {
+        (None, visible_entries(path, &rules.enter(path)).count())
+    };
+
+    FileNode {
+        name: path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        path: path.to_string_lossy().to_string(),
+        is_dir: metadata.is_dir(),
+        size: if metadata.is_file() {
+            metadata.len()
+        } else {
+            0
+        },
+        children,
+        has_children: child_count > 0,
+        child_count,
+    }
+}
+
+/// Build the node for `path` as the tree would show it, or `None` if it is
+/// missing or ignored. `rules` are the rules of the workspace root.
+pub fn node_for_path(path: &Path, rules: &IgnoreRules) -> Option<FileNode> {
+    let metadata = fs::metadata(path).ok()?;
+
+    if rules.matched_path(path, metadata.is_dir()).is_some() {
+        return None;
+    }
+
+    let parent_rules = match path.parent() {
+        Some(parent) => rules.descend(parent),
+        None => rules.clone(),
+    };
+
+    Some(build_node(
+        path,
+        &metadata,
+        &parent_rules,
+        None,
+        &JobContext::detached(),
+    ))
+}
+
+fn scan_level(
+    dir: &Path,
+    rules: &IgnoreRules,
+    depth: Option<usize>,
+    job: &JobContext,
+) -> Vec<FileNode> {
+    let mut nodes = Vec::new();
+
+    for entry in visible_entries(dir, rules) {
+        if job.is_cancelled() {
+            break;
         }
+
+        let path = entry.path();
+        job.record(path, 0);
+
+        let metadata = match entry.metadata() {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+
+        let node = build_node(path, &metadata, rules, depth, job);
+        nodes.push(node);
     }
 
     nodes.sort_by(|a, b| {
@@ -160,16 +302,16 @@ pub fn scan_directory(
         }
     });
 
-    Ok(nodes)
+    nodes
 }
 
-pub fn get_git_tracked_files(repo_path: &str) -> Result<Vec<String>, String> {
-    let repo =
-        Repository::open(repo_path).map_err(|e| format!("Failed to open git repository: {}", e))?;
+pub fn get_git_tracked_files(repo_path: &str) -> Result<Vec<String>, AppError> {
+    let repo = open_repository(repo_path)?;
 
-    let index = repo
-        .index()
-        .map_err(|e| format!("Failed to read git index: {}", e))?;
+    let index = repo.index().map_err(|e| {
+        AppError::new(ErrorKind::Io, format!("Failed to read git index: {}", e))
+            .with_path(repo_path)
+    })?;
 
     let mut tracked_files = Vec::new();
     let repo_path_buf = std::path::PathBuf::from(repo_path);
@@ -184,61 +326,184 @@ pub fn get_git_tracked_files(repo_path: &str) -> Result<Vec<String>, String> {
     Ok(tracked_files)
 }
 
-pub fn read_file_contents(request: &CopyRequest) -> Result<String, String> {
-    let base_path = Path::new(&request.base_path);
-    let max_size = request.max_file_size_mb.unwrap_or(10) * 1024 * 1024; // Convert MB to bytes
+pub fn read_file_contents(request: &CopyRequest) -> Result<String, AppError> {
+    read_file_contents_with(request, &JobContext::detached())
+}
 
-    match request.format.as_str() {
-        "markdown" => format_as_markdown(request, base_path, max_size),
-        "json" => format_as_json(request, base_path, max_size),
-        "xml" => format_as_xml(request, base_path, max_size),
-        _ => format_as_markdown(request, base_path, max_size),
+/// Read and format files as part of a background job.
+///
+/// In block mode this fails with `SecretsFound` unless the request is confirmed.
+pub fn read_file_contents_with(
+    request: &CopyRequest,
+    job: &JobContext,
+) -> Result<String, AppError> {
+    let output = read_with_findings(request, job)?;
+    if let Some(scan) = &request.secret_scan {
+        ensure_confirmed(&output.findings, scan, request.secrets_confirmed)?;
     }
+    Ok(output.content)
+}
+
+/// Read and format files, returning the secret findings instead of enforcing block mode
+pub fn read_with_findings(request: &CopyRequest, job: &JobContext) -> Result<ReadOutput, AppError> {
+    let base_path = Path::new(&request.base_path);
+    let max_size = max_file_size(request);
+
+    let files = visible_files(request)?;
+    let mut loaded = load_files(&files, base_path, max_size, job)?;
+    let findings = prepare_loaded(&mut loaded, request)?;
+
+    Ok(ReadOutput {
+        content: format_output(
+            &loaded,
+            project_tree(request)?.as_deref(),
+            &request.format,
+            max_size,
+        )?,
+        findings,
+        secret_mode: request
+            .secret_scan
+            .as_ref()
+            .map_or(SecretMode::Off, |scan| scan.mode),
+    })
 }
 
-fn format_as_markdown(
+/// Turn loaded files into what will be copied: git history if asked for,
+/// diffs for the `diff` format or selected slices and skeletons otherwise,
+/// then the request's secret scan, redacting them if configured
+pub fn prepare_loaded(
+    files: &mut Vec<LoadedFile>,
     request: &CopyRequest,
-    base_path: &Path,
-    max_size: u64,
-) -> Result<String, String> {
-    let mut output = String::new();
+) -> Result<Vec<SecretFinding>, AppError> {
+    // Blame needs the file contents, so it runs before they become diffs
+    annotate_loaded(files, Path::new(&request.base_path), request.git_metadata)?;
+
+    if request.format == "diff" {
+        let settings = request.diff.clone().unwrap_or_default();
+        diff_loaded(
+            files,
+            Path::new(&request.base_path),
+            &settings,
+            max_file_size(request),
+        )?;
+    } else {
+        *files = slice_loaded(std::mem::take(files), &request.selections);
+        if let Some(skeleton) = &request.skeleton {
+            skeleton_loaded(files, skeleton);
+        }
+    }
 
-    for file_path in &request.files {
-        let path = Path::new(file_path);
+    match &request.secret_scan {
+        Some(scan) => scan_loaded(files, scan),
+        None => Ok(Vec::new()),
+    }
+}
 
-        if !path.exists() || !path.is_file() {
-            continue;
-        }
+pub fn max_file_size(request: &CopyRequest) -> u64 {
+    request.max_file_size_mb.unwrap_or(10) * 1024 * 1024 // Convert MB to bytes
+}
 
-        if is_binary_file(path) {
-            continue;
+/// `files` followed by the files that only appear in `selections`
+pub fn requested_files(request: &CopyRequest) -> Vec<String> {
+    let mut files = request.files.clone();
+    for selection in &request.selections {
+        if !files.contains(&selection.file) {
+            files.push(selection.file.clone());
         }
+    }
+    files
+}
 
-        // Check file size
-        if let Ok(metadata) = path.metadata() {
-            if metadata.len() > max_size {
-                let relative_path = get_relative_path(path, base_path);
+/// The requested files that are not hidden from the tree.
+///
+/// Files hidden from the tree must never end up in the output.
+pub fn visible_files(request: &CopyRequest) -> Result<Vec<String>, AppError> {
+    let rules = IgnoreRules::new(Path::new(&request.base_path), &request.ignore_patterns)?;
+
+    Ok(requested_files(request)
+        .iter()
+        .filter(|f| rules.matched_path(Path::new(f), false).is_none())
+        .cloned()
+        .collect())
+}
+
+/// The project tree the request asked for, if any
+pub fn project_tree(request: &CopyRequest) -> Result<Option<String>, AppError> {
+    request
+        .tree
+        .as_ref()
+        .map(|options| render_tree(request, options))
+        .transpose()
+}
+
+/// Format files that were already read by the pipeline
+pub fn format_loaded(
+    files: &[LoadedFile],
+    format: &str,
+    max_size: u64,
+) -> Result<String, AppError> {
+    format_output(files, None, format, max_size)
+}
+
+/// Format files with an optional project tree ahead of them
+pub fn format_output(
+    files: &[LoadedFile],
+    tree: Option<&str>,
+    format: &str,
+    max_size: u64,
+) -> Result<String, AppError> {
+    match format {
+        "json" => format_as_json(files, tree),
+        "xml" => format_as_xml(files, tree),
+        "diff" => Ok(markdown_tree(tree) + &format_as_diff(files, max_size)?),
+        _ => Ok(markdown_tree(tree) + &format_as_markdown(files, max_size)?),
+    }
+}
+
+fn markdown_tree(tree: Option<&str>) -> String {
+    tree.map(|tree| format!("Project structure :\n```\n{}```\n\n", tree))
+        .unwrap_or_default()
+}
+
+/// The relative path, followed by the line range for a slice
+fn display_path(file: &LoadedFile) -> Cow<'_, str> {
+    match file.lines {
+        Some((start, end)) => Cow::Owned(format!("{}:{}-{}", file.relative_path, start, end)),
+        None => Cow::Borrowed(&file.relative_path),
+    }
+}
+
+fn format_as_markdown(files: &[LoadedFile], max_size: u64) -> Result<String, AppError> {
+    let mut output = String::new();
+
+    for file in files {
+        let relative_path = display_path(file);
+
+        match &file.body {
+            FileBody::Text(content) => {
+                let escaped_content = content.replace("```", "\\`\\`\\`");
                 output.push_str(&format!(
-                    "{} : [File too large: {} bytes, max: {} bytes]\n\n",
+                    "{} :\n{}```\n{}\n```\n\n",
                     relative_path,
-                    metadata.len(),
-                    max_size
+                    history_header(file),
+                    escaped_content
                 ));
-                continue;
             }
-        }
-
-        let relative_path = get_relative_path(path, base_path);
-
-        match fs::read_to_string(path) {
-            Ok(content) => {
-                let escaped_content = content.replace("```", "\\`\\`\\`");
+            FileBody::Binary => {}
+            FileBody::TooLarge => {
                 output.push_str(&format!(
-                    "{} :\n```\n{}\n```\n\n",
-                    relative_path, escaped_content
+                    "{} : [File too large: {} bytes, max: {} bytes]\n\n",
+                    relative_path, file.size, max_size
                 ));
             }
-            Err(e) => {
+            // Selected files deleted since the tree was loaded are flagged, not dropped
+            FileBody::Missing => {
+                output.push_str(&format!(
+                    "{} : [File not found: it may have been deleted]\n\n",
+                    relative_path
+                ));
+            }
+            FileBody::Error(e) => {
                 output.push_str(&format!(
                     "{} : [Error reading file: {}]\n\n",
                     relative_path, e
@@ -250,189 +515,283 @@ fn format_as_markdown(
     Ok(output)
 }
 
-fn format_as_json(
-    request: &CopyRequest,
-    base_path: &Path,
-    max_size: u64,
-) -> Result<String, String> {
-    #[derive(Serialize)]
-    struct FileContent {
-        path: String,
-        content: Option<String>,
-        error: Option<String>,
-        size: u64,
-    }
+/// Files already turned into diffs by [`prepare_loaded`]; unchanged ones are left out
+fn format_as_diff(files: &[LoadedFile], max_size: u64) -> Result<String, AppError> {
+    let mut output = String::new();
 
-    let mut files_data = Vec::new();
+    for file in files {
+        match &file.body {
+            FileBody::Text(diff) if diff.is_empty() => {}
+            FileBody::Text(diff) => {
+                output.push_str(&format!(
+                    "{} :\n{}```diff\n{}```\n\n",
+                    display_path(file),
+                    history_header(file),
+                    diff.replace("```", "\\`\\`\\`")
+                ));
+            }
+            _ => output.push_str(&format_as_markdown(std::slice::from_ref(file), max_size)?),
+        }
+    }
 
-    for file_path in &request.files {
-        let path = Path::new(file_path);
+    Ok(output)
+}
 
-        if !path.exists() || !path.is_file() {
-            continue;
+/// Lines describing a file's last commit and blame, empty without history
+fn history_header(file: &LoadedFile) -> String {
+    let Some(history) = &file.history else {
+        return String::new();
+    };
+
+    let mut header = match &history.last_commit {
+        Some(commit) => format!(
+            "Last commit: {} by {} <{}> on {}: {}\n",
+            short_hash(&commit.commit),
+            commit.author,
+            commit.email,
+            commit.date,
+            commit.subject
+        ),
+        None => "Last commit: none, the file is not committed\n".to_string(),
+    };
+
+    if !history.blame.is_empty() {
+        header.push_str("Blame:\n");
+        for range in &history.blame {
+            let lines = format!("{}-{}", range.start_line, range.end_line);
+            match &range.commit {
+                Some(commit) => header.push_str(&format!(
+                    "  {} {} {} {}: {}\n",
+                    lines,
+                    short_hash(&commit.commit),
+                    commit.author,
+                    commit.date.get(..10).unwrap_or(&commit.date),
+                    commit.subject
+                )),
+                None => header.push_str(&format!("  {} not committed\n", lines)),
+            }
         }
+    }
 
-        let relative_path = get_relative_path(path, base_path);
-        let metadata = path.metadata().ok();
-        let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-
-        if is_binary_file(path) {
-            files_data.push(FileContent {
-                path: relative_path,
-                content: None,
-                error: Some("Binary file skipped".to_string()),
-                size,
-            });
-            continue;
-        }
+    header
+}
 
-        if size > max_size {
-            files_data.push(FileContent {
-                path: relative_path,
-                content: None,
-                error: Some(format!("File too large: {} bytes", size)),
-                size,
-            });
-            continue;
-        }
+fn short_hash(hash: &str) -> &str {
+    hash.get(..10).unwrap_or(hash)
+}
 
-        match fs::read_to_string(path) {
-            Ok(content) => {
-                files_data.push(FileContent {
-                    path: relative_path,
-                    content: Some(content),
-                    error: None,
-                    size,
-                });
-            }
-            Err(e) => {
-                files_data.push(FileContent {
-                    path: relative_path,
-                    content: None,
-                    error: Some(e.to_string()),
-                    size,
-                });
-            }
-        }
+fn format_as_json(files: &[LoadedFile], tree: Option<&str>) -> Result<String, AppError> {
+    #[derive(Serialize)]
+    struct FileContent<'a> {
+        path: &'a str,
+        content: Option<&'a str>,
+        error: Option<String>,
+        size: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        line_start: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        line_end: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        git: Option<&'a FileHistory>,
     }
 
-    serde_json::to_string_pretty(&files_data)
-        .map_err(|e| format!("Failed to serialize to JSON: {}", e))
+    let files_data: Vec<FileContent> = files
+        .iter()
+        .map(|file| {
+            let (content, error) = match &file.body {
+                FileBody::Text(content) => (Some(content.as_str()), None),
+                FileBody::Binary => (None, Some("Binary file skipped".to_string())),
+                FileBody::TooLarge => (None, Some(format!("File too large: {} bytes", file.size))),
+                FileBody::Missing => (None, Some("File not found".to_string())),
+                FileBody::Error(e) => (None, Some(e.clone())),
+            };
+
+            FileContent {
+                path: &file.relative_path,
+                content,
+                error,
+                size: file.size,
+                line_start: file.lines.map(|(start, _)| start),
+                line_end: file.lines.map(|(_, end)| end),
+                git: file.history.as_ref(),
+            }
+        })
+        .collect();
+
+    // Without a tree the output stays a plain array of files
+    let result = match tree {
+        Some(tree) => serde_json::to_string_pretty(&json!({ "tree": tree, "files": files_data })),
+        None => serde_json::to_string_pretty(&files_data),
+    };
+    result.map_err(|e| AppError::internal(format!("Failed to serialize to JSON: {}", e)))
 }
 
-fn format_as_xml(request: &CopyRequest, base_path: &Path, max_size: u64) -> Result<String, String> {
+fn format_as_xml(files: &[LoadedFile], tree: Option<&str>) -> Result<String, AppError> {
     let mut writer = Writer::new(Cursor::new(Vec::new()));
 
     writer
         .write_event(Event::Start(BytesStart::new("files")))
-        .map_err(|e| format!("XML error: {}", e))?;
+        .map_err(xml_error)?;
 
-    for file_path in &request.files {
-        let path = Path::new(file_path);
+    if let Some(tree) = tree {
+        writer
+            .write_event(Event::Start(BytesStart::new("tree")))
+            .map_err(xml_error)?;
+        writer
+            .write_event(Event::Text(BytesText::new(tree)))
+            .map_err(xml_error)?;
+        writer
+            .write_event(Event::End(BytesEnd::new("tree")))
+            .map_err(xml_error)?;
+    }
 
-        if !path.exists() || !path.is_file() {
-            continue;
+    for file in files {
+        let mut file_elem = BytesStart::new("file");
+        file_elem.push_attribute(("path", file.relative_path.as_str()));
+        file_elem.push_attribute(("size", file.size.to_string().as_str()));
+        if let Some((start, end)) = file.lines {
+            file_elem.push_attribute(("line_start", start.to_string().as_str()));
+            file_elem.push_attribute(("line_end", end.to_string().as_str()));
         }
 
-        let relative_path = get_relative_path(path, base_path);
-        let metadata = path.metadata().ok();
-        let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+        writer
+            .write_event(Event::Start(file_elem))
+            .map_err(xml_error)?;
+        if let Some(history) = &file.history {
+            write_history_xml(&mut writer, history)?;
+        }
 
-        let mut file_elem = BytesStart::new("file");
-        file_elem.push_attribute(("path", relative_path.as_str()));
-        file_elem.push_attribute(("size", size.to_string().as_str()));
+        let (tag, text): (&str, Cow<str>) = match &file.body {
+            FileBody::Text(content) => ("content", Cow::Borrowed(content)),
+            FileBody::Binary => ("error", Cow::Borrowed("Binary file skipped")),
+            FileBody::TooLarge => (
+                "error",
+                Cow::Owned(format!("File too large: {} bytes", file.size)),
+            ),
+            FileBody::Missing => ("error", Cow::Borrowed("File not found")),
+            FileBody::Error(e) => ("error", Cow::Borrowed(e)),
+        };
 
         writer
-            .write_event(Event::Start(file_elem.clone()))
-            .map_err(|e| format!("XML error: {}", e))?;
-
-        if is_binary_file(path) {
-            writer
-                .write_event(Event::Start(BytesStart::new("error")))
-                .map_err(|e| format!("XML error: {}", e))?;
-            writer
-                .write_event(Event::Text(BytesText::new("Binary file skipped")))
-                .map_err(|e| format!("XML error: {}", e))?;
-            writer
-                .write_event(Event::End(BytesEnd::new("error")))
-                .map_err(|e| format!("XML error: {}", e))?;
-        } else if size > max_size {
-            writer
-                .write_event(Event::Start(BytesStart::new("error")))
-                .map_err(|e| format!("XML error: {}", e))?;
-            writer
-                .write_event(Event::Text(BytesText::new(&format!(
-                    "File too large: {} bytes",
-                    size
-                ))))
-                .map_err(|e| format!("XML error: {}", e))?;
-            writer
-                .write_event(Event::End(BytesEnd::new("error")))
-                .map_err(|e| format!("XML error: {}", e))?;
-        } else {
-            match fs::read_to_string(path) {
-                Ok(content) => {
-                    writer
-                        .write_event(Event::Start(BytesStart::new("content")))
-                        .map_err(|e| format!("XML error: {}", e))?;
-                    writer
-                        .write_event(Event::Text(BytesText::new(&content)))
-                        .map_err(|e| format!("XML error: {}", e))?;
-                    writer
-                        .write_event(Event::End(BytesEnd::new("content")))
-                        .map_err(|e| format!("XML error: {}", e))?;
-                }
-                Err(e) => {
-                    writer
-                        .write_event(Event::Start(BytesStart::new("error")))
-                        .map_err(|e| format!("XML error: {}", e))?;
-                    writer
-                        .write_event(Event::Text(BytesText::new(&e.to_string())))
-                        .map_err(|e| format!("XML error: {}", e))?;
-                    writer
-                        .write_event(Event::End(BytesEnd::new("error")))
-                        .map_err(|e| format!("XML error: {}", e))?;
-                }
-            }
-        }
+            .write_event(Event::Start(BytesStart::new(tag)))
+            .map_err(xml_error)?;
+        writer
+            .write_event(Event::Text(BytesText::new(&text)))
+            .map_err(xml_error)?;
+        writer
+            .write_event(Event::End(BytesEnd::new(tag)))
+            .map_err(xml_error)?;
 
         writer
             .write_event(Event::End(BytesEnd::new("file")))
-            .map_err(|e| format!("XML error: {}", e))?;
+            .map_err(xml_error)?;
     }
 
     writer
         .write_event(Event::End(BytesEnd::new("files")))
-        .map_err(|e| format!("XML error: {}", e))?;
+        .map_err(xml_error)?;
 
     let result = writer.into_inner().into_inner();
-    String::from_utf8(result).map_err(|e| format!("Failed to convert XML to string: {}", e))
+    String::from_utf8(result)
+        .map_err(|e| AppError::internal(format!("Failed to convert XML to string: {}", e)))
 }
 
-fn get_relative_path(path: &Path, base_path: &Path) -> String {
-    match path.strip_prefix(base_path) {
-        Ok(p) => p.to_string_lossy().to_string(),
-        Err(_) => path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string(),
+fn write_history_xml(
+    writer: &mut Writer<Cursor<Vec<u8>>>,
+    history: &FileHistory,
+) -> Result<(), AppError> {
+    let mut git = BytesStart::new("git");
+    if let Some(commit) = &history.last_commit {
+        git.push_attribute(("commit", commit.commit.as_str()));
+        git.push_attribute(("author", commit.author.as_str()));
+        git.push_attribute(("email", commit.email.as_str()));
+        git.push_attribute(("date", commit.date.as_str()));
+        git.push_attribute(("subject", commit.subject.as_str()));
     }
+    writer.write_event(Event::Start(git)).map_err(xml_error)?;
+
+    for range in &history.blame {
+        let mut blame = BytesStart::new("blame");
+        blame.push_attribute(("start_line", range.start_line.to_string().as_str()));
+        blame.push_attribute(("end_line", range.end_line.to_string().as_str()));
+        if let Some(commit) = &range.commit {
+            blame.push_attribute(("commit", commit.commit.as_str()));
+            blame.push_attribute(("author", commit.author.as_str()));
+            blame.push_attribute(("date", commit.date.as_str()));
+            blame.push_attribute(("subject", commit.subject.as_str()));
+        }
+        writer.write_event(Event::Empty(blame)).map_err(xml_error)?;
+    }
+
+    writer
+        .write_event(Event::End(BytesEnd::new("git")))
+        .map_err(xml_error)?;
+    Ok(())
 }
 
-pub fn export_to_file(request: &ExportRequest) -> Result<(), String> {
-    fs::write(&request.path, &request.content).map_err(|e| format!("Failed to export file: {}", e))
+fn xml_error(error: impl std::fmt::Display) -> AppError {
+    AppError::internal(format!("XML error: {}", error))
 }
 
-pub fn copy_to_clipboard(content: &str) -> Result<(), String> {
+pub fn export_to_file(request: &ExportRequest) -> Result<(), AppError> {
+    fs::write(&request.path, &request.content)
+        .map_err(|e| AppError::io(e, "Failed to export", &request.path))
+}
+
+pub fn copy_to_clipboard(content: &str) -> Result<(), AppError> {
     use arboard::Clipboard;
 
-    let mut clipboard =
-        Clipboard::new().map_err(|e| format!("Failed to access clipboard: {}", e))?;
+    let mut clipboard = Clipboard::new()
+        .map_err(|e| AppError::internal(format!("Failed to access clipboard: {}", e)))?;
 
     clipboard
         .set_text(content)
-        .map_err(|e| format!("Failed to copy to clipboard: {}", e))?;
+        .map_err(|e| AppError::internal(format!("Failed to copy to clipboard: {}", e)))?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_directory_loads_one_level() {
+        let dir = std::env::temp_dir().join(format!("filesurfer_list_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let src = dir.join("src");
+        fs::create_dir_all(src.join("nested").join("deeper")).unwrap();
+        fs::create_dir_all(src.join("logs")).unwrap();
+        fs::write(src.join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(src.join("debug.log"), "").unwrap();
+        fs::write(src.join("nested").join("a.rs"), "").unwrap();
+        fs::write(src.join("nested").join("b.log"), "").unwrap();
+        let root = dir.to_string_lossy().to_string();
+        let patterns = vec!["*.log".to_string(), "logs/".to_string()];
+
+        let nodes = list_directory(&root, &src.to_string_lossy(), &patterns).unwrap();
+        let listed: Vec<(&str, bool, bool, usize)> = nodes
+            .iter()
+            .map(|node| {
+                (
+                    node.name.as_str(),
+                    node.children.is_none(),
+                    node.has_children,
+                    node.child_count,
+                )
+            })
+            .collect();
+        // Subdirectories are counted, not loaded, and ignored entries are not counted
+        assert_eq!(
+            listed,
+            vec![("nested", true, true, 2), ("main.rs", true, false, 0)]
+        );
+
+        let ignored = list_directory(&root, &src.join("logs").to_string_lossy(), &patterns);
+        assert_eq!(ignored.unwrap_err().kind, ErrorKind::InvalidInput);
+        let outside = list_directory(&src.to_string_lossy(), &root, &patterns);
+        assert_eq!(outside.unwrap_err().kind, ErrorKind::OutsideWorkspace);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,869 @@
+use git2::{
+    Delta, Diff, DiffFindOptions, DiffOptions, ErrorCode, Oid, Patch, Repository, Sort, Time, Tree,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, ErrorKind};
+use crate::read_pipeline::{FileBody, LoadedFile};
+
+/// Which changes to list
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChangeSource {
+    /// Tracked files with unstaged edits
+    WorkingTree,
+    /// Changes added to the index
+    Staged,
+    /// New files that are not ignored
+    Untracked,
+    /// Everything that differs from `base`, committed or not. With
+    /// `merge_base` the comparison starts where the branch forked from it.
+    Base {
+        base: String,
+        #[serde(default)]
+        merge_base: bool,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeStatus {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Copied,
+    TypeChanged,
+    Untracked,
+    Conflicted,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ChangedFile {
+    pub path: String,
+    pub relative_path: String,
+    pub status: ChangeStatus,
+    /// The previous path of a renamed or copied file
+    pub old_path: Option<String>,
+}
+
+/// How the `diff` output format compares the selected files
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiffSettings {
+    /// The ref the working tree is compared with
+    #[serde(default = "default_base")]
+    pub base: String,
+    #[serde(default = "default_context_lines")]
+    pub context_lines: u32,
+    /// Show the whole file with its changed hunks marked instead of only the hunks
+    #[serde(default)]
+    pub full_file: bool,
+}
+
+fn default_base() -> String {
+    "HEAD".to_string()
+}
+
+fn default_context_lines() -> u32 {
+    3
+}
+
+impl Default for DiffSettings {
+    fn default() -> Self {
+        Self {
+            base: default_base(),
+            context_lines: default_context_lines(),
+            full_file: false,
+        }
+    }
+}
+
+/// Which git history to attach to each copied file
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GitMetadata {
+    #[default]
+    Off,
+    LastCommit,
+    /// The last commit plus which commit last touched each range of lines
+    Blame,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct CommitInfo {
+    pub commit: String,
+    pub author: String,
+    pub email: String,
+    /// ISO 8601 in the author's time zone
+    pub date: String,
+    pub subject: String,
+}
+
+/// Consecutive lines last changed by the same commit, or not committed yet
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct BlameRange {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub commit: Option<CommitInfo>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FileHistory {
+    /// `None` for files that were never committed
+    pub last_commit: Option<CommitInfo>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub blame: Vec<BlameRange>,
+}
+
+pub fn open_repository(repo_path: &str) -> Result<Repository, AppError> {
+    Repository::open(repo_path).map_err(|e| open_error(e, repo_path))
+}
+
+fn open_error(e: git2::Error, path: &str) -> AppError {
+    let kind = if e.code() == ErrorCode::NotFound {
+        ErrorKind::NotAGitRepo
+    } else {
+        ErrorKind::Io
+    };
+    AppError::new(kind, format!("Failed to open git repository: {}", e)).with_path(path)
+}
+
+fn git_error(action: &str, repo_path: &str, e: git2::Error) -> AppError {
+    AppError::new(ErrorKind::Io, format!("Failed to {}: {}", action, e)).with_path(repo_path)
+}
+
+/// List changed files with their status, sorted by path. `repo_path` may be
+/// a directory inside the repository, such as one package of a monorepo; only
+/// changes under it are listed, with paths relative to it.
+pub fn get_changed_files(
+    repo_path: &str,
+    source: &ChangeSource,
+) -> Result<Vec<ChangedFile>, AppError> {
+    let repo = Repository::discover(repo_path).map_err(|e| open_error(e, repo_path))?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| AppError::invalid("Bare repositories have no working tree"))?
+        .to_path_buf();
+    let base = repo_relative(Path::new(repo_path), &workdir).ok_or_else(|| {
+        AppError::invalid("Not inside the repository's working tree").with_path(repo_path)
+    })?;
+
+    let mut options = DiffOptions::new();
+    let diff = match source {
+        ChangeSource::WorkingTree => repo.diff_index_to_workdir(None, Some(&mut options)),
+        ChangeSource::Untracked => {
+            options.include_untracked(true).recurse_untracked_dirs(true);
+            repo.diff_index_to_workdir(None, Some(&mut options))
+        }
+        ChangeSource::Staged => {
+            let head = head_tree(&repo, repo_path)?;
+            repo.diff_tree_to_index(head.as_ref(), None, Some(&mut options))
+        }
+        ChangeSource::Base { base, merge_base } => {
+            let tree = base_tree(&repo, repo_path, base, *merge_base)?;
+            repo.diff_tree_to_workdir_with_index(Some(&tree), Some(&mut options))
+        }
+    }
+    .map_err(|e| git_error("compare changes", repo_path, e))?;
+
+    let diff = find_renames(diff, repo_path)?;
+    let untracked_only = matches!(source, ChangeSource::Untracked);
+
+    let mut files: Vec<ChangedFile> = diff
+        .deltas()
+        .filter(|delta| (delta.status() == Delta::Untracked) == untracked_only)
+        .filter_map(|delta| {
+            let status = match delta.status() {
+                Delta::Added => ChangeStatus::Added,
+                Delta::Modified => ChangeStatus::Modified,
+                Delta::Deleted => ChangeStatus::Deleted,
+                Delta::Renamed => ChangeStatus::Renamed,
+                Delta::Copied => ChangeStatus::Copied,
+                Delta::Typechange => ChangeStatus::TypeChanged,
+                Delta::Untracked => ChangeStatus::Untracked,
+                Delta::Conflicted => ChangeStatus::Conflicted,
+                _ => return None,
+            };
+            let new_path = delta.new_file().path()?.strip_prefix(&base).ok()?;
+            // A file moved in from elsewhere in the repository keeps its full path
+            let old_path = delta
+                .old_file()
+                .path()
+                .filter(|_| matches!(status, ChangeStatus::Renamed | ChangeStatus::Copied))
+                .map(|old| old.strip_prefix(&base).unwrap_or(old));
+
+            Some(ChangedFile {
+                path: Path::new(repo_path)
+                    .join(new_path)
+                    .to_string_lossy()
+                    .to_string(),
+                relative_path: slash_path(new_path),
+                status,
+                old_path: old_path.map(slash_path),
+            })
+        })
+        .collect();
+
+    files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok(files)
+}
+
+fn find_renames<'r>(mut diff: Diff<'r>, repo_path: &str) -> Result<Diff<'r>, AppError> {
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))
+        .map_err(|e| git_error("detect renames", repo_path, e))?;
+    Ok(diff)
+}
+
+/// The tree of HEAD, or `None` before the first commit
+fn head_tree<'r>(repo: &'r Repository, repo_path: &str) -> Result<Option<Tree<'r>>, AppError> {
+    match repo.head() {
+        Ok(head) => head
+            .peel_to_tree()
+            .map(Some)
+            .map_err(|e| git_error("read HEAD", repo_path, e)),
+        Err(e) if e.code() == ErrorCode::UnbornBranch || e.code() == ErrorCode::NotFound => {
+            Ok(None)
+        }
+        Err(e) => Err(git_error("read HEAD", repo_path, e)),
+    }
+}
+
+fn base_tree<'r>(
+    repo: &'r Repository,
+    repo_path: &str,
+    base: &str,
+    merge_base: bool,
+) -> Result<Tree<'r>, AppError> {
+    let base_commit = repo
+        .revparse_single(base)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| AppError::invalid(format!("Unknown git ref '{}': {}", base, e)))?;
+
+    let commit = if merge_base {
+        let head = repo
+            .head()
+            .and_then(|head| head.peel_to_commit())
+            .map_err(|e| git_error("read HEAD", repo_path, e))?;
+        let fork = repo.merge_base(base_commit.id(), head.id()).map_err(|e| {
+            AppError::invalid(format!("No merge base between HEAD and '{}': {}", base, e))
+        })?;
+        repo.find_commit(fork)
+            .map_err(|e| git_error("read the merge base", repo_path, e))?
+    } else {
+        base_commit
+    };
+
+    commit
+        .tree()
+        .map_err(|e| git_error("read the base tree", repo_path, e))
+}
+
+/// Replace the body of each loaded file with its unified diff against
+/// `settings.base`. Unchanged files are left with an empty body, deleted ones
+/// get a diff removing every line, and files git cannot compare keep their body.
+pub fn diff_loaded(
+    files: &mut [LoadedFile],
+    base_path: &Path,
+    settings: &DiffSettings,
+    max_size: u64,
+) -> Result<(), AppError> {
+    let path_str = base_path.to_string_lossy();
+    let repo = Repository::discover(base_path).map_err(|e| open_error(e, &path_str))?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| AppError::invalid("Bare repositories have no working tree"))?
+        .to_path_buf();
+    let tree = ref_tree(&repo, &path_str, &settings.base)?;
+
+    for file in files {
+        let new = match &file.body {
+            FileBody::Text(content) => content.clone(),
+            FileBody::Missing => String::new(),
+            _ => continue,
+        };
+        let Some(relative) = repo_relative(&file.path, &workdir) else {
+            file.body = FileBody::Error("Not inside the git repository".to_string());
+            continue;
+        };
+
+        let blob = tree
+            .as_ref()
+            .and_then(|tree| tree.get_path(&relative).ok())
+            .and_then(|entry| entry.to_object(&repo).ok())
+            .and_then(|object| object.peel_to_blob().ok());
+        let old = match &blob {
+            Some(blob) if blob.size() as u64 > max_size => {
+                file.body = FileBody::TooLarge;
+                continue;
+            }
+            Some(blob) if blob.is_binary() => {
+                file.body = FileBody::Binary;
+                continue;
+            }
+            Some(blob) => String::from_utf8_lossy(blob.content()).to_string(),
+            // Neither in the base nor on disk
+            None if matches!(file.body, FileBody::Missing) => continue,
+            None => String::new(),
+        };
+
+        let context_lines = if settings.full_file {
+            (old.lines().count() + new.lines().count()) as u32
+        } else {
+            settings.context_lines
+        };
+        file.body = FileBody::Text(unified_diff(&old, &new, &relative, context_lines)?);
+    }
+
+    Ok(())
+}
+
+fn unified_diff(
+    old: &str,
+    new: &str,
+    relative: &Path,
+    context_lines: u32,
+) -> Result<String, AppError> {
+    let mut options = DiffOptions::new();
+    options.context_lines(context_lines);
+    let mut patch = Patch::from_buffers(
+        old.as_bytes(),
+        Some(relative),
+        new.as_bytes(),
+        Some(relative),
+        Some(&mut options),
+    )
+    .map_err(|e| AppError::internal(format!("Failed to diff {}: {}", relative.display(), e)))?;
+
+    if patch.num_hunks() == 0 {
+        return Ok(String::new());
+    }
+    let text = patch
+        .to_buf()
+        .map_err(|e| AppError::internal(format!("Failed to diff {}: {}", relative.display(), e)))?;
+    Ok(String::from_utf8_lossy(&text).to_string())
+}
+
+/// The tree at `base`, or `None` when it is HEAD of a repository with no commits
+fn ref_tree<'r>(
+    repo: &'r Repository,
+    repo_path: &str,
+    base: &str,
+) -> Result<Option<Tree<'r>>, AppError> {
+    if base == "HEAD" {
+        return head_tree(repo, repo_path);
+    }
+    repo.revparse_single(base)
+        .and_then(|object| object.peel_to_tree())
+        .map(Some)
+        .map_err(|e| AppError::invalid(format!("Unknown git ref '{}': {}", base, e)))
+}
+
+/// Attach the last commit, and the blame when asked, to each loaded file.
+/// Files outside a git repository are left without history.
+pub fn annotate_loaded(
+    files: &mut [LoadedFile],
+    base_path: &Path,
+    metadata: GitMetadata,
+) -> Result<(), AppError> {
+    if metadata == GitMetadata::Off {
+        return Ok(());
+    }
+    let Ok(repo) = Repository::discover(base_path) else {
+        return Ok(());
+    };
+    let Some(workdir) = repo.workdir().map(Path::to_path_buf) else {
+        return Ok(());
+    };
+    let path_str = base_path.to_string_lossy();
+
+    let relatives: Vec<Option<PathBuf>> = files
+        .iter()
+        .map(|file| repo_relative(&file.path, &workdir))
+        .collect();
+    let last = last_commits(&repo, &path_str, relatives.iter().flatten())?;
+    let mut commits = HashMap::new();
+
+    for (file, relative) in files.iter_mut().zip(&relatives) {
+        let Some(relative) = relative else {
+            continue;
+        };
+        let blame = match &file.body {
+            FileBody::Text(content) if metadata == GitMetadata::Blame => {
+                blame_ranges(&repo, relative, content, &mut commits)
+            }
+            _ => Vec::new(),
+        };
+
+        file.history = Some(FileHistory {
+            last_commit: last
+                .get(relative)
+                .and_then(|oid| commit_info(&repo, *oid, &mut commits)),
+            blame,
+        });
+    }
+
+    Ok(())
+}
+
+/// The newest commit that changed each path, found in one walk from HEAD.
+///
+/// Like `git log --first-parent`, only the first-parent chain is followed and
+/// each commit is compared with its first parent, so a file changed on a
+/// merged branch is attributed to the merge commit.
+fn last_commits<'p>(
+    repo: &Repository,
+    repo_path: &str,
+    paths: impl Iterator<Item = &'p PathBuf>,
+) -> Result<HashMap<PathBuf, Oid>, AppError> {
+    let mut found = HashMap::new();
+    let Some(head) = head_tree(repo, repo_path)? else {
+        return Ok(found);
+    };
+    // Paths missing from HEAD would walk the whole history for nothing
+    let mut pending: HashSet<&Path> = paths
+        .filter(|path| head.get_path(path).is_ok())
+        .map(PathBuf::as_path)
+        .collect();
+
+    let mut walk = repo
+        .revwalk()
+        .map_err(|e| git_error("walk history", repo_path, e))?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)
+        .and_then(|_| walk.simplify_first_parent())
+        .and_then(|_| walk.push_head())
+        .map_err(|e| git_error("walk history", repo_path, e))?;
+
+    for oid in walk {
+        if pending.is_empty() {
+            break;
+        }
+        let commit = oid
+            .and_then(|oid| repo.find_commit(oid))
+            .map_err(|e| git_error("walk history", repo_path, e))?;
+        let tree = commit
+            .tree()
+            .map_err(|e| git_error("walk history", repo_path, e))?;
+        let parent = commit.parent(0).ok().and_then(|parent| parent.tree().ok());
+
+        pending.retain(|path| {
+            let now = tree.get_path(path).ok().map(|entry| entry.id());
+            let before = parent
+                .as_ref()
+                .and_then(|parent| parent.get_path(path).ok())
+                .map(|entry| entry.id());
+            if now == before {
+                return true;
+            }
+            found.insert(path.to_path_buf(), commit.id());
+            false
+        });
+    }
+
+    Ok(found)
+}
+
+/// Blame of the file as loaded, so uncommitted edits show as such
+fn blame_ranges(
+    repo: &Repository,
+    relative: &Path,
+    content: &str,
+    commits: &mut HashMap<Oid, Option<CommitInfo>>,
+) -> Vec<BlameRange> {
+    // Not tracked yet
+    let Ok(committed) = repo.blame_file(relative, None) else {
+        return Vec::new();
+    };
+    let Ok(blame) = committed.blame_buffer(content.as_bytes()) else {
+        return Vec::new();
+    };
+
+    let mut ranges: Vec<(usize, usize, Oid)> = Vec::new();
+    for hunk in blame.iter() {
+        let start = hunk.final_start_line();
+        let end = start + hunk.lines_in_hunk() - 1;
+        let oid = hunk.final_commit_id();
+        match ranges.last_mut() {
+            Some(last) if last.2 == oid && last.1 + 1 == start => last.1 = end,
+            _ => ranges.push((start, end, oid)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start_line, end_line, oid)| BlameRange {
+            start_line,
+            end_line,
+            commit: if oid.is_zero() {
+                None
+            } else {
+                commit_info(repo, oid, commits)
+            },
+        })
+        .collect()
+}
+
+fn commit_info(
+    repo: &Repository,
+    oid: Oid,
+    commits: &mut HashMap<Oid, Option<CommitInfo>>,
+) -> Option<CommitInfo> {
+    commits
+        .entry(oid)
+        .or_insert_with(|| {
+            let commit = repo.find_commit(oid).ok()?;
+            let author = commit.author();
+            Some(CommitInfo {
+                commit: oid.to_string(),
+                author: author.name().unwrap_or_default().to_string(),
+                email: author.email().unwrap_or_default().to_string(),
+                date: format_time(author.when()),
+                subject: commit.summary().unwrap_or_default().to_string(),
+            })
+        })
+        .clone()
+}
+
+fn format_time(time: Time) -> String {
+    let offset = time.offset_minutes() as i64;
+    let local = time.seconds() + offset * 60;
+    let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+    let seconds = local.rem_euclid(86_400);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+/// Year, month and day of a count of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn repo_relative(path: &Path, workdir: &Path) -> Option<PathBuf> {
+    if let Ok(relative) = path.strip_prefix(workdir) {
+        return Some(relative.to_path_buf());
+    }
+    // Symlinked checkouts, such as /tmp on macOS
+    let path = path.canonicalize().ok()?;
+    let workdir = workdir.canonicalize().ok()?;
+    path.strip_prefix(workdir).ok().map(Path::to_path_buf)
+}
+
+fn slash_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+    use std::fs;
+
+    fn commit_all(repo: &Repository, message: &str) {
+        commit_merge(repo, message, None);
+    }
+
+    /// Commit everything on top of HEAD, with `merged` as a second parent
+    fn commit_merge(repo: &Repository, message: &str, merged: Option<&git2::Commit>) {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.update_all(["*"].iter(), None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature =
+            Signature::new("Test", "test@example.com", &Time::new(1_700_000_000, 60)).unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<&git2::Commit> = parent.iter().chain(merged).collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap();
+    }
+
+    /// A repository with a branch point, a committed rename and uncommitted,
+    /// staged and untracked changes on top
+    fn fixture(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("filesurfer_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let repo = Repository::init(&dir).unwrap();
+
+        fs::write(dir.join("kept.rs"), "fn kept() {}\n").unwrap();
+        fs::write(dir.join("edited.rs"), "fn edited() {}\n").unwrap();
+        fs::write(
+            dir.join("moved.rs"),
+            "fn moved() {\n    let long_enough = 1;\n}\n",
+        )
+        .unwrap();
+        commit_all(&repo, "base");
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("main-base", &base, false).unwrap();
+
+        // A commit on the branch, then uncommitted work on top
+        fs::rename(dir.join("moved.rs"), dir.join("renamed.rs")).unwrap();
+        commit_all(&repo, "rename");
+        fs::write(dir.join("edited.rs"), "fn edited() { todo!() }\n").unwrap();
+        fs::write(dir.join("staged.rs"), "fn staged() {}\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("staged.rs")).unwrap();
+        index.write().unwrap();
+        fs::create_dir_all(dir.join("new")).unwrap();
+        fs::write(dir.join("new").join("untracked.rs"), "").unwrap();
+
+        dir
+    }
+
+    fn load(dir: &Path, relative: &str, content: &str) -> LoadedFile {
+        LoadedFile {
+            path: dir.join(relative),
+            relative_path: relative.to_string(),
+            size: content.len() as u64,
+            body: FileBody::Text(content.to_string()),
+            history: None,
+            lines: None,
+        }
+    }
+
+    fn summary(files: &[ChangedFile]) -> Vec<(&str, ChangeStatus)> {
+        files
+            .iter()
+            .map(|f| (f.relative_path.as_str(), f.status))
+            .collect()
+    }
+
+    #[test]
+    fn test_changed_files() {
+        let dir = fixture("git_changes");
+        let repo_path = dir.to_string_lossy().to_string();
+
+        let working = get_changed_files(&repo_path, &ChangeSource::WorkingTree).unwrap();
+        assert_eq!(
+            summary(&working),
+            vec![("edited.rs", ChangeStatus::Modified)]
+        );
+        assert_eq!(
+            working[0].path,
+            dir.join("edited.rs").to_string_lossy().to_string()
+        );
+
+        let staged = get_changed_files(&repo_path, &ChangeSource::Staged).unwrap();
+        assert_eq!(summary(&staged), vec![("staged.rs", ChangeStatus::Added)]);
+
+        let untracked = get_changed_files(&repo_path, &ChangeSource::Untracked).unwrap();
+        assert_eq!(
+            summary(&untracked),
+            vec![("new/untracked.rs", ChangeStatus::Untracked)]
+        );
+
+        let branch = get_changed_files(
+            &repo_path,
+            &ChangeSource::Base {
+                base: "main-base".to_string(),
+                merge_base: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            summary(&branch),
+            vec![
+                ("edited.rs", ChangeStatus::Modified),
+                ("renamed.rs", ChangeStatus::Renamed),
+                ("staged.rs", ChangeStatus::Added),
+            ]
+        );
+        assert_eq!(branch[1].old_path.as_deref(), Some("moved.rs"));
+
+        let unknown = ChangeSource::Base {
+            base: "no-such-ref".to_string(),
+            merge_base: false,
+        };
+        assert_eq!(
+            get_changed_files(&repo_path, &unknown).unwrap_err().kind,
+            ErrorKind::InvalidInput
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_diff_format() {
+        let dir = fixture("git_diff");
+
+        // The diff format compares with HEAD, so only uncommitted edits show
+        let mut files = vec![
+            load(&dir, "edited.rs", "fn edited() { todo!() }\n"),
+            load(&dir, "kept.rs", "fn kept() {}\n"),
+            load(&dir, "new/untracked.rs", "fn fresh() {}\n"),
+        ];
+        diff_loaded(&mut files, &dir, &DiffSettings::default(), 1024).unwrap();
+        let bodies: Vec<String> = files
+            .iter()
+            .map(|file| match &file.body {
+                FileBody::Text(text) => text.clone(),
+                other => panic!("unexpected body {:?}", other),
+            })
+            .collect();
+        assert!(bodies[0].contains("-fn edited() {}\n+fn edited() { todo!() }"));
+        assert!(bodies[0].contains("+++ b/edited.rs"));
+        assert_eq!(bodies[1], "");
+        assert!(bodies[2].contains("+fn fresh() {}"));
+
+        let against_base = DiffSettings {
+            base: "main-base".to_string(),
+            context_lines: 0,
+            full_file: true,
+        };
+        let mut files = vec![load(
+            &dir,
+            "renamed.rs",
+            "fn moved() {\n    let long_enough = 2;\n}\n",
+        )];
+        diff_loaded(&mut files, &dir, &against_base, 1024).unwrap();
+        let FileBody::Text(full) = &files[0].body else {
+            panic!("expected text");
+        };
+        // A file new since the base, shown whole
+        assert!(full.contains("+fn moved() {\n+    let long_enough = 2;\n+}"));
+
+        let mut files = vec![load(&dir, "edited.rs", "fn edited() {}\n")];
+        diff_loaded(&mut files, &dir, &DiffSettings::default(), 4).unwrap();
+        assert!(matches!(files[0].body, FileBody::TooLarge));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_last_commit_and_blame() {
+        let dir = fixture("git_history");
+
+        let mut files = vec![
+            load(&dir, "edited.rs", "fn edited() { todo!() }\n"),
+            load(
+                &dir,
+                "renamed.rs",
+                "fn moved() {\n    let long_enough = 1;\n}\n",
+            ),
+            load(&dir, "new/untracked.rs", ""),
+        ];
+        annotate_loaded(&mut files, &dir, GitMetadata::Blame).unwrap();
+        let edited = files[0].history.as_ref().unwrap();
+        let base_commit = edited.last_commit.as_ref().unwrap();
+        assert_eq!(base_commit.subject, "base");
+        assert_eq!(base_commit.author, "Test");
+        assert_eq!(base_commit.date, "2023-11-14T23:13:20+01:00");
+        assert_eq!(
+            edited.blame,
+            vec![BlameRange {
+                start_line: 1,
+                end_line: 1,
+                commit: None,
+            }]
+        );
+        let renamed = files[1].history.as_ref().unwrap();
+        assert_eq!(renamed.last_commit.as_ref().unwrap().subject, "rename");
+        assert_eq!(renamed.blame.len(), 1);
+        assert_eq!(renamed.blame[0].end_line, 3);
+        assert!(files[2].history.as_ref().unwrap().last_commit.is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn test_changes_in_repository_subdirectory() {
+        let dir = std::env::temp_dir().join(format!("filesurfer_git_sub_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("packages").join("app")).unwrap();
+        let repo = Repository::init(&dir).unwrap();
+
+        let package = dir.join("packages").join("app");
+        fs::write(package.join("main.js"), "main();\n").unwrap();
+        fs::write(dir.join("root.js"), "root();\n").unwrap();
+        commit_all(&repo, "base");
+        fs::write(package.join("main.js"), "main(1);\n").unwrap();
+        fs::write(dir.join("root.js"), "root(1);\n").unwrap();
+
+        let package_path = package.to_string_lossy().to_string();
+        let changed = get_changed_files(&package_path, &ChangeSource::WorkingTree).unwrap();
+        assert_eq!(summary(&changed), vec![("main.js", ChangeStatus::Modified)]);
+        assert_eq!(
+            changed[0].path,
+            package.join("main.js").to_string_lossy().to_string()
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn test_last_commit_follows_first_parent() {
+        let dir = std::env::temp_dir().join(format!("filesurfer_git_merge_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let repo = Repository::init(&dir).unwrap();
+
+        for name in ["side.rs", "main.rs", "reverted.rs"] {
+            fs::write(dir.join(name), "// base\n").unwrap();
+        }
+        commit_all(&repo, "base");
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+
+        fs::write(dir.join("side.rs"), "// side\n").unwrap();
+        fs::write(dir.join("reverted.rs"), "// side\n").unwrap();
+        commit_all(&repo, "side");
+        let side = repo.head().unwrap().peel_to_commit().unwrap();
+
+        repo.reset(base.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+        fs::write(dir.join("main.rs"), "// main\n").unwrap();
+        commit_all(&repo, "main");
+        // The merge takes the side branch's side.rs but keeps reverted.rs as it was
+        fs::write(dir.join("side.rs"), "// side\n").unwrap();
+        commit_merge(&repo, "merge", Some(&side));
+
+        let mut files = vec![
+            load(&dir, "side.rs", "// side\n"),
+            load(&dir, "main.rs", "// main\n"),
+            load(&dir, "reverted.rs", "// base\n"),
+        ];
+        annotate_loaded(&mut files, &dir, GitMetadata::LastCommit).unwrap();
+        let subjects: Vec<&str> = files
+            .iter()
+            .map(|file| {
+                let history = file.history.as_ref().unwrap();
+                history.last_commit.as_ref().unwrap().subject.as_str()
+            })
+            .collect();
+        assert_eq!(subjects, vec!["merge", "main", "base"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
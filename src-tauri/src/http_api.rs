@@ -0,0 +1,443 @@
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+use crate::code_analysis::{detect_dependencies, extract_functions, extract_todos};
+use crate::config::load_model_registry;
+use crate::error::{AppError, ErrorKind};
+use crate::file_ops::{read_file_contents, scan_directory, CopyRequest};
+use crate::path_policy::{PathError, PathPolicy};
+use crate::secrets::use_configured_scan;
+use crate::token_counter::estimate_tokens;
+
+/// Files sent by an integration to be selected in the GUI
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PushedSelection {
+    pub base_path: Option<String>,
+    pub files: Vec<String>,
+}
+
+/// Where the API is listening, returned to the settings panel
+#[derive(Debug, Serialize, Clone)]
+pub struct HttpApiInfo {
+    pub port: u16,
+    pub token: String,
+}
+
+/// What every connection needs to answer requests
+pub struct ApiContext {
+    pub token: String,
+    pub policy: PathPolicy,
+    pub on_select: Box<dyn Fn(PushedSelection) + Send + Sync>,
+}
+
+// Pause after a failed accept, such as running out of file descriptors
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+struct RunningApi {
+    info: HttpApiInfo,
+    shutdown: oneshot::Sender<()>,
+    /// Finishes once the listener is closed
+    server: JoinHandle<()>,
+}
+
+/// The opt-in localhost API, managed as Tauri state
+#[derive(Default)]
+pub struct HttpApi {
+    active: Mutex<Option<RunningApi>>,
+}
+
+#[derive(Deserialize)]
+struct ScanArgs {
+    path: String,
+    #[serde(default)]
+    custom_patterns: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ReadArgs {
+    request: CopyRequest,
+}
+
+#[derive(Deserialize)]
+struct TokensArgs {
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct FileArgs {
+    file_path: String,
+}
+
+impl HttpApi {
+    /// Listen on `127.0.0.1:port`, replacing any running server. The old
+    /// server is shut down first, so restarting on the same port works.
+    ///
+    /// Port 0 picks a free port; the one in use is returned.
+    pub fn start<F>(
+        &self,
+        port: u16,
+        token: &str,
+        policy: PathPolicy,
+        on_select: F,
+    ) -> Result<HttpApiInfo, AppError>
+    where
+        F: Fn(PushedSelection) + Send + Sync + 'static,
+    {
+        self.stop();
+
+        let listener = std::net::TcpListener::bind(("127.0.0.1", port))
+            .map_err(|e| socket_error(format!("Failed to bind port {}: {}", port, e)))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| socket_error(format!("Failed to configure listener: {}", e)))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| socket_error(format!("Failed to read listener address: {}", e)))?
+            .port();
+
+        let context = Arc::new(ApiContext {
+            token: token.to_string(),
+            policy,
+            on_select: Box::new(on_select),
+        });
+        let (shutdown, stopped) = oneshot::channel();
+
+        let server = std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(_) => return,
+            };
+            runtime.block_on(async move {
+                if let Ok(listener) = TcpListener::from_std(listener) {
+                    serve(listener, context, stopped).await;
+                }
+            });
+        });
+
+        let info = HttpApiInfo {
+            port,
+            token: token.to_string(),
+        };
+        let mut active = self
+            .active
+            .lock()
+            .map_err(|_| AppError::internal("HTTP API is unavailable"))?;
+        *active = Some(RunningApi {
+            info: info.clone(),
+            shutdown,
+            server,
+        });
+
+        Ok(info)
+    }
+
+    /// Stop the running server and wait until its port is released
+    pub fn stop(&self) {
+        let running = match self.active.lock() {
+            Ok(mut active) => active.take(),
+            Err(_) => None,
+        };
+        if let Some(running) = running {
+            let _ = running.shutdown.send(());
+            let _ = running.server.join();
+        }
+    }
+
+    pub fn info(&self) -> Option<HttpApiInfo> {
+        self.active
+            .lock()
+            .ok()
+            .and_then(|active| active.as_ref().map(|running| running.info.clone()))
+    }
+}
+
+/// Accept connections until `shutdown` fires
+pub async fn serve(
+    listener: TcpListener,
+    context: Arc<ApiContext>,
+    mut shutdown: oneshot::Receiver<()>,
+) {
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    eprintln!("HTTP API failed to accept a connection: {}", e);
+                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+
+        let context = context.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |request| handle(request, context.clone()));
+            let _ = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await;
+        });
+    }
+}
+
+async fn handle(
+    request: Request<Incoming>,
+    context: Arc<ApiContext>,
+) -> Result<Response<Full<Bytes>>, hyper::Error> {
+    let path = request.uri().path().to_string();
+
+    if path == "/health" {
+        return Ok(respond(StatusCode::OK, json!({ "ok": true })));
+    }
+    if !authorized(&request, &context.token) {
+        return Ok(error(
+            StatusCode::UNAUTHORIZED,
+            AppError::new(ErrorKind::PermissionDenied, "Missing or invalid token"),
+        ));
+    }
+    if request.method() != Method::POST {
+        return Ok(error(
+            StatusCode::METHOD_NOT_ALLOWED,
+            AppError::invalid("Use POST"),
+        ));
+    }
+
+    let body = request.into_body().collect().await?.to_bytes();
+    let response = tokio::task::spawn_blocking(move || dispatch(&path, &body, &context))
+        .await
+        .unwrap_or_else(|e| {
+            error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                AppError::internal(e.to_string()),
+            )
+        });
+
+    Ok(response)
+}
+
+fn authorized(request: &Request<Incoming>, token: &str) -> bool {
+    let provided = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or("");
+
+    // Compare every byte so timing does not reveal a matching prefix
+    provided.len() == token.len()
+        && !token.is_empty()
+        && provided
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Run the command behind a route, mirroring the Tauri command of the same name
+fn dispatch(path: &str, body: &[u8], context: &ApiContext) -> Response<Full<Bytes>> {
+    let policy = &context.policy;
+
+    match path {
+        "/scan" => call(body, |args: ScanArgs| {
+            policy.check(&args.path)?;
+            scan_directory(&args.path, args.custom_patterns)
+        }),
+        "/read_files" => call(body, |mut args: ReadArgs| {
+            policy.check_request(&args.request)?;
+            use_configured_scan(&mut args.request)?;
+            read_file_contents(&args.request).map(|content| json!({ "content": content }))
+        }),
+        "/tokens" => call(body, |args: TokensArgs| {
+            estimate_tokens(&args.content, &load_model_registry()?.models)
+        }),
+        "/dependencies" => call(body, |args: FileArgs| {
+            policy.check(&args.file_path)?;
+            detect_dependencies(&args.file_path)
+        }),
+        "/functions" => call(body, |args: FileArgs| {
+            policy.check(&args.file_path)?;
+            extract_functions(&args.file_path)
+        }),
+        "/todos" => call(body, |args: FileArgs| {
+            policy.check(&args.file_path)?;
+            extract_todos(&args.file_path)
+        }),
+        "/select" => call(body, |selection: PushedSelection| {
+            let base = selection
+                .base_path
+                .as_deref()
+                .map(|base_path| policy.check(base_path))
+                .transpose()?;
+            for file in &selection.files {
+                let resolved = policy.check(file)?;
+                if let (Some(base), Some(base_path)) = (&base, &selection.base_path) {
+                    if !resolved.starts_with(base) {
+                        return Err(PathError::OutsideBasePath {
+                            path: file.clone(),
+                            base_path: base_path.clone(),
+                        }
+                        .into());
+                    }
+                }
+            }
+            let count = selection.files.len();
+            (context.on_select)(selection);
+            Ok(json!({ "selected": count }))
+        }),
+        _ => error(
+            StatusCode::NOT_FOUND,
+            AppError::not_found(format!("Unknown endpoint: {}", path)),
+        ),
+    }
+}
+
+fn call<A, T, F>(body: &[u8], command: F) -> Response<Full<Bytes>>
+where
+    A: DeserializeOwned,
+    T: Serialize,
+    F: FnOnce(A) -> Result<T, AppError>,
+{
+    let args = match serde_json::from_slice(body) {
+        Ok(args) => args,
+        Err(e) => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                AppError::invalid(format!("Invalid request: {}", e)),
+            )
+        }
+    };
+
+    let result = command(args).and_then(|result| {
+        serde_json::to_value(result).map_err(|e| AppError::internal(e.to_string()))
+    });
+    match result {
+        Ok(result) => respond(StatusCode::OK, result),
+        Err(e) => error(status_for(e.kind), e),
+    }
+}
+
+fn status_for(kind: ErrorKind) -> StatusCode {
+    match kind {
+        ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        ErrorKind::PermissionDenied | ErrorKind::OutsideWorkspace => StatusCode::FORBIDDEN,
+        ErrorKind::InvalidInput | ErrorKind::NotAGitRepo => StatusCode::BAD_REQUEST,
+        ErrorKind::Cancelled => StatusCode::CONFLICT,
+        ErrorKind::SecretsFound => StatusCode::UNPROCESSABLE_ENTITY,
+        ErrorKind::Io | ErrorKind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn socket_error(message: String) -> AppError {
+    AppError::new(ErrorKind::Io, message)
+}
+
+fn error(status: StatusCode, error: AppError) -> Response<Full<Bytes>> {
+    respond(status, json!({ "error": error }))
+}
+
+fn respond(status: StatusCode, body: Value) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(body.to_string())));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::mpsc;
+
+    fn post(port: u16, path: &str, token: &str, body: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            path,
+            token,
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn test_api_requires_token_and_pushes_selection() {
+        let dir = std::env::temp_dir().join(format!("filesurfer_http_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.rs"), "// TODO: wire up\nfn main() {}\n").unwrap();
+        let file = dir.join("main.rs").to_string_lossy().to_string();
+
+        let (sender, pushed) = mpsc::channel();
+        let api = HttpApi::default();
+        let policy = PathPolicy::new(&[dir.to_string_lossy().to_string()]).unwrap();
+        let info = api
+            .start(0, "secret", policy, move |selection| {
+                sender.send(selection).unwrap();
+            })
+            .unwrap();
+
+        let body = json!({ "file_path": file }).to_string();
+        assert_eq!(post(info.port, "/todos", "wrong", &body).0, 401);
+
+        let (status, todos) = post(info.port, "/todos", "secret", &body);
+        assert_eq!(status, 200);
+        assert_eq!(todos[0]["message"], "wire up");
+
+        let body = json!({ "files": [file] }).to_string();
+        let (status, result) = post(info.port, "/select", "secret", &body);
+        assert_eq!((status, result["selected"].as_u64()), (200, Some(1)));
+        assert_eq!(pushed.recv().unwrap().files, vec![file.clone()]);
+
+        fs::create_dir_all(dir.join("src")).unwrap();
+        let base_path = dir.join("src").to_string_lossy().to_string();
+        let body = json!({ "files": [file], "base_path": base_path }).to_string();
+        let (status, refused) = post(info.port, "/select", "secret", &body);
+        assert_eq!(status, 403);
+        assert_eq!(refused["error"]["kind"], "outside_workspace");
+
+        let outside = json!({ "file_path": "/etc/hosts" }).to_string();
+        let (status, refused) = post(info.port, "/todos", "secret", &outside);
+        assert_eq!(status, 403);
+        assert_eq!(refused["error"]["kind"], "outside_workspace");
+
+        assert_eq!(post(info.port, "/missing", "secret", "{}").0, 404);
+
+        // Restarting frees the port before binding it again
+        let policy = PathPolicy::new(&[dir.to_string_lossy().to_string()]).unwrap();
+        let restarted = api.start(info.port, "other", policy, |_| {}).unwrap();
+        assert_eq!(restarted.port, info.port);
+        let body = json!({ "file_path": file }).to_string();
+        assert_eq!(post(info.port, "/todos", "other", &body).0, 200);
+
+        api.stop();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,427 @@
+use ignore::gitignore::{gitconfig_excludes_path, Gitignore, GitignoreBuilder};
+use ignore::Match;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::error::AppError;
+
+/// The rule that caused a path to be hidden from the tree
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IgnoreMatch {
+    pub pattern: String,
+    pub source: Option<String>, // file the rule was read from, if any
+    pub kind: String,           // "custom", "default", "gitignore", "ignore", "exclude", "global"
+}
+
+// Default ignore patterns, in gitignore syntax
+const DEFAULT_PATTERNS: &[&str] = &[
+    "node_modules/",
+    ".git/",
+    "dist/",
+    "build/",
+    "target/",
+    ".next/",
+    "out/",
+    "coverage/",
+    ".cache/",
+    ".vscode/",
+    ".idea/",
+    "__pycache__/",
+    "*.pyc",
+    ".DS_Store",
+    "Thumbs.db",
+];
+
+#[derive(Clone)]
+struct Layer {
+    kind: &'static str,
+    matcher: Arc<Gitignore>,
+}
+
+/// Stack of ignore rules that apply to a directory.
+///
+/// Custom patterns win over the built-in defaults, which win over ignore
+/// files. Deeper directories take precedence over shallower ones, and within
+/// a single directory `.ignore` takes precedence over `.gitignore`, matching
+/// the behaviour of git and ripgrep.
+///
+/// Clones share a cache of the ignore files already read per directory, so
+/// matching many paths does not read them again.
+#[derive(Clone)]
+pub struct IgnoreRules {
+    root: PathBuf,
+    pattern_layers: Vec<Layer>,
+    dir_layers: Vec<Layer>,
+    base_layers: Vec<Layer>,
+    dir_cache: Arc<Mutex<HashMap<PathBuf, Vec<Layer>>>>,
+}
+
+impl IgnoreRules {
+    /// Build the rules for `root`, including every ignore file between the
+    /// enclosing git repository (if any) and `root` itself.
+    ///
+    /// `custom_patterns` use gitignore syntax and are anchored at `root`.
+    pub fn new(root: &Path, custom_patterns: &[String]) -> Result<Self, AppError> {
+        Self::with_global(root, custom_patterns, gitconfig_excludes_path())
+    }
+
+    /// Like [`IgnoreRules::new`], with the global excludes file given explicitly
+    fn with_global(
+        root: &Path,
+        custom_patterns: &[String],
+        global: Option<PathBuf>,
+    ) -> Result<Self, AppError> {
+        let pattern_layers = vec![
+            pattern_layer(root, custom_patterns, "custom")?,
+            pattern_layer(root, DEFAULT_PATTERNS, "default")?,
+        ];
+
+        let repo_root = find_repo_root(root);
+        let mut base_layers = Vec::new();
+
+        if let Some(repo_root) = &repo_root {
+            if let Some(git_dir) = git_dir(repo_root) {
+                let exclude = git_dir.join("info").join("exclude");
+                if let Some(layer) = load_layer(repo_root, &exclude, "exclude") {
+                    base_layers.push(layer);
+                }
+            }
+        }
+
+        if let Some(global) = global {
+            let global_root = repo_root.as_deref().unwrap_or(root);
+            if let Some(layer) = load_layer(global_root, &global, "global") {
+                base_layers.push(layer);
+            }
+        }
+
+        let mut rules = IgnoreRules {
+            root: root.to_path_buf(),
+            pattern_layers,
+            dir_layers: Vec::new(),
+            base_layers,
+            dir_cache: Arc::default(),
+        };
+
+        let start = repo_root.as_deref().unwrap_or(root);
+        for dir in dirs_between(start, root) {
+            rules.push_dir(&dir);
+        }
+
+        Ok(rules)
+    }
+
+    /// Rules for a child directory, adding its own ignore files.
+    pub fn enter(&self, dir: &Path) -> Self {
+        let mut rules = self.clone();
+        rules.push_dir(dir);
+        rules
+    }
+
+    /// Rules for `dir`, a directory anywhere below the root.
+    pub fn descend(&self, dir: &Path) -> Self {
+        let mut rules = self.clone();
+        if let Ok(relative) = dir.strip_prefix(&self.root) {
+            let mut current = self.root.clone();
+            for component in relative.components() {
+                current.push(component);
+                rules.push_dir(&current);
+            }
+        }
+        rules
+    }
+
+    /// Return the rule that ignores `path`, or `None` if it should be shown.
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Option<IgnoreMatch> {
+        let layers = self
+            .pattern_layers
+            .iter()
+            .chain(self.dir_layers.iter().rev())
+            .chain(self.base_layers.iter());
+
+        for layer in layers {
+            match layer.matcher.matched(path, is_dir) {
+                Match::Ignore(glob) => {
+                    return Some(IgnoreMatch {
+                        pattern: glob.original().to_string(),
+                        source: glob.from().map(|p| p.to_string_lossy().to_string()),
+                        kind: layer.kind.to_string(),
+                    })
+                }
+                Match::Whitelist(_) => return None,
+                Match::None => {}
+            }
+        }
+        None
+    }
+
+    /// Like [`IgnoreRules::matched`], but for any path below the root.
+    ///
+    /// Walks down from the root so that an ignored ancestor hides everything
+    /// below it, exactly as the tree does. Paths outside the root are only
+    /// checked against the rules themselves.
+    pub fn matched_path(&self, path: &Path, is_dir: bool) -> Option<IgnoreMatch> {
+        let relative = match path.strip_prefix(&self.root) {
+            Ok(relative) => relative,
+            Err(_) => return self.matched(path, is_dir),
+        };
+
+        let mut rules = self.clone();
+        let mut current = self.root.clone();
+        let components: Vec<_> = relative.components().collect();
+
+        for component in components.iter().take(components.len().saturating_sub(1)) {
+            current.push(component);
+            if let Some(hit) = rules.matched(&current, true) {
+                return Some(hit);
+            }
+            rules.push_dir(&current);
+        }
+
+        rules.matched(path, is_dir)
+    }
+
+    fn push_dir(&mut self, dir: &Path) {
+        let layers = match self.dir_cache.lock() {
+            Ok(mut cache) => cache
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| load_dir_layers(dir))
+                .clone(),
+            Err(_) => load_dir_layers(dir),
+        };
+        self.dir_layers.extend(layers);
+    }
+}
+
+/// The layers for the ignore files directly inside `dir`
+fn load_dir_layers(dir: &Path) -> Vec<Layer> {
+    [(".gitignore", "gitignore"), (".ignore", "ignore")]
+        .into_iter()
+        .filter_map(|(name, kind)| load_layer(dir, &dir.join(name), kind))
+        .collect()
+}
+
+fn pattern_layer<S: AsRef<str>>(
+    root: &Path,
+    patterns: &[S],
+    kind: &'static str,
+) -> Result<Layer, AppError> {
+    let mut builder = GitignoreBuilder::new(root);
+
+    for pattern in patterns {
+        builder.add_line(None, pattern.as_ref()).map_err(|e| {
+            AppError::invalid(format!(
+                "Invalid ignore pattern '{}': {}",
+                pattern.as_ref(),
+                e
+            ))
+        })?;
+    }
+
+    let matcher = builder
+        .build()
+        .map_err(|e| AppError::invalid(format!("Failed to build ignore patterns: {}", e)))?;
+
+    Ok(Layer {
+        kind,
+        matcher: Arc::new(matcher),
+    })
+}
+
+fn load_layer(root: &Path, file: &Path, kind: &'static str) -> Option<Layer> {
+    if !file.is_file() {
+        return None;
+    }
+
+    let mut builder = GitignoreBuilder::new(root);
+    // Partial errors still yield the valid globs, so keep going
+    builder.add(file);
+    let matcher = builder.build().ok()?;
+
+    if matcher.is_empty() {
+        return None;
+    }
+
+    Some(Layer {
+        kind,
+        matcher: Arc::new(matcher),
+    })
+}
+
+fn find_repo_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(|dir| dir.to_path_buf())
+}
+
+/// The git directory of the repository at `repo_root`.
+///
+/// In worktrees and submodules `.git` is a file pointing at it, and worktrees
+/// share `info/` with the main repository through `commondir`.
+fn git_dir(repo_root: &Path) -> Option<PathBuf> {
+    let dot_git = repo_root.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+
+    let content = fs::read_to_string(&dot_git).ok()?;
+    let git_dir = repo_root.join(content.strip_prefix("gitdir:")?.trim());
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common) => Some(git_dir.join(common.trim())),
+        Err(_) => Some(git_dir),
+    }
+}
+
+/// Every directory from `start` down to `end`, inclusive.
+fn dirs_between(start: &Path, end: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = end
+        .ancestors()
+        .take_while(|dir| dir.starts_with(start))
+        .map(|dir| dir.to_path_buf())
+        .collect();
+    dirs.reverse();
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_tree(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("filesurfer_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("pkg").join("gen")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_nested_gitignore() {
+        let root = temp_tree("nested_gitignore");
+        fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(root.join("pkg").join(".gitignore"), "gen/\n!keep.log\n").unwrap();
+
+        let rules = IgnoreRules::new(&root, &[]).unwrap();
+        let pkg = rules.enter(&root.join("pkg"));
+
+        let hit = rules.matched(&root.join("debug.log"), false).unwrap();
+        assert_eq!(hit.pattern, "*.log");
+        assert_eq!(hit.kind, "gitignore");
+        assert!(pkg.matched(&root.join("pkg").join("gen"), true).is_some());
+        assert!(pkg
+            .matched(&root.join("pkg").join("keep.log"), false)
+            .is_none());
+        assert!(rules.matched(&root.join("pkg"), true).is_none());
+
+        // Matching below the root reads each directory's ignore files once
+        assert!(rules
+            .matched_path(&root.join("pkg").join("gen").join("a.rs"), false)
+            .is_some());
+        assert!(rules
+            .dir_cache
+            .lock()
+            .unwrap()
+            .contains_key(&root.join("pkg")));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_exclude_and_global_files() {
+        let root = temp_tree("exclude_files");
+        fs::create_dir_all(root.join(".git").join("info")).unwrap();
+        fs::write(root.join(".git").join("info").join("exclude"), "*.tmp\n").unwrap();
+        let global = root.join("global_ignore");
+        fs::write(&global, "*.bak\n").unwrap();
+
+        let rules = IgnoreRules::with_global(&root, &[], Some(global)).unwrap();
+        let kind = |rules: &IgnoreRules, path: PathBuf| rules.matched(&path, false).unwrap().kind;
+        assert_eq!(kind(&rules, root.join("a.tmp")), "exclude");
+        assert_eq!(kind(&rules, root.join("a.bak")), "global");
+
+        // A worktree's `.git` file points into the main repository, which
+        // holds the shared `info/exclude`
+        let worktree = root.join("pkg").join("gen");
+        let worktree_git = root.join(".git").join("worktrees").join("gen");
+        fs::create_dir_all(&worktree_git).unwrap();
+        fs::write(worktree_git.join("commondir"), "../..\n").unwrap();
+        fs::write(
+            worktree.join(".git"),
+            format!("gitdir: {}\n", worktree_git.display()),
+        )
+        .unwrap();
+        let rules = IgnoreRules::with_global(&worktree, &[], None).unwrap();
+        assert_eq!(kind(&rules, worktree.join("a.tmp")), "exclude");
+
+        // Submodules use a relative `gitdir`
+        let submodule_git = root.join(".git").join("modules").join("pkg");
+        fs::create_dir_all(submodule_git.join("info")).unwrap();
+        fs::write(submodule_git.join("info").join("exclude"), "*.out\n").unwrap();
+        fs::write(
+            root.join("pkg").join(".git"),
+            "gitdir: ../.git/modules/pkg\n",
+        )
+        .unwrap();
+        let rules = IgnoreRules::with_global(&root.join("pkg"), &[], None).unwrap();
+        assert_eq!(kind(&rules, root.join("pkg").join("a.out")), "exclude");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_ignore_file_overrides_gitignore() {
+        let root = temp_tree("ignore_precedence");
+        fs::write(root.join(".gitignore"), "*.snap\n").unwrap();
+        fs::write(root.join(".ignore"), "!*.snap\n").unwrap();
+
+        let rules = IgnoreRules::new(&root, &[])
+            .unwrap()
+            .enter(&root.join("pkg"));
+        assert!(rules
+            .matched(&root.join("pkg").join("a.snap"), false)
+            .is_none());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_custom_glob_patterns() {
+        let root = temp_tree("custom_globs");
+        let patterns = vec![
+            "/build".to_string(),
+            "src/**/*.snap".to_string(),
+            "*.log".to_string(),
+            "!keep.log".to_string(),
+            "out".to_string(),
+            "tmp[0-9]/".to_string(),
+        ];
+        let rules = IgnoreRules::new(&root, &patterns).unwrap();
+
+        let ignored =
+            |rel: &str, is_dir: bool| rules.matched_path(&root.join(rel), is_dir).is_some();
+
+        assert!(ignored("build", true));
+        assert!(!ignored("pkg/build", false));
+        assert!(ignored("src/a/b/c.snap", false));
+        assert!(!ignored("c.snap", false));
+        assert!(ignored("debug.log", false));
+        assert!(!ignored("keep.log", false));
+        assert!(!ignored("layout.rs", false));
+        assert!(ignored("out/main.js", false));
+        assert!(ignored("tmp1", true));
+        assert!(!ignored("tmp1", false));
+        assert!(ignored("node_modules/react/index.js", false));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let root = temp_tree("invalid_pattern");
+        assert!(IgnoreRules::new(&root, &["src/{a,b".to_string()]).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,441 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Component, Path, PathBuf};
+
+use crate::code_analysis::{detect_dependencies, Dependency};
+use crate::error::AppError;
+
+/// Files reachable by imports from a set of seeds
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ImportGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    /// Local-looking imports that match no file
+    pub unresolved: Vec<ImportRef>,
+    /// Packages, the standard library and other crates
+    pub external: Vec<ImportRef>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct GraphNode {
+    pub path: String,
+    /// Import hops from the nearest seed; seeds are 0
+    pub depth: usize,
+    /// False when the depth limit stopped its imports from being followed
+    pub expanded: bool,
+    /// Why its imports could not be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<AppError>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    /// The import as written
+    pub dependency: String,
+    pub line_number: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportRef {
+    pub file: String,
+    pub dependency: String,
+    pub line_number: usize,
+}
+
+enum Target {
+    File(PathBuf),
+    External,
+    Unresolved,
+}
+
+/// Follow imports breadth-first from `seeds` for up to `max_depth` hops.
+///
+/// Every file gets one node at its shortest distance from a seed and is read
+/// once, so cycles end where they close. Files `allowed` rejects are left out
+/// of the graph entirely.
+pub fn build_graph(
+    seeds: &[String],
+    max_depth: usize,
+    allowed: impl Fn(&str) -> bool,
+) -> ImportGraph {
+    let mut graph = ImportGraph::default();
+    let mut index: HashMap<PathBuf, usize> = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    let mut add_node = |graph: &mut ImportGraph, path: PathBuf, depth: usize| {
+        if index.contains_key(&path) {
+            return None;
+        }
+        index.insert(path.clone(), graph.nodes.len());
+        graph.nodes.push(GraphNode {
+            path: path.to_string_lossy().to_string(),
+            depth,
+            expanded: false,
+            error: None,
+        });
+        Some(graph.nodes.len() - 1)
+    };
+
+    for seed in seeds {
+        if let Some(node) = add_node(&mut graph, normalize(Path::new(seed)), 0) {
+            queue.push_back(node);
+        }
+    }
+
+    let mut edges = HashSet::new();
+    let mut refs = HashSet::new();
+    while let Some(node) = queue.pop_front() {
+        if graph.nodes[node].depth >= max_depth {
+            continue;
+        }
+        let file = graph.nodes[node].path.clone();
+        let dependencies = match detect_dependencies(&file) {
+            Ok(dependencies) => dependencies,
+            Err(error) => {
+                graph.nodes[node].error = Some(error);
+                continue;
+            }
+        };
+        graph.nodes[node].expanded = true;
+
+        for dependency in &dependencies {
+            for (import, target) in resolve_dependency(Path::new(&file), dependency) {
+                let reference = || ImportRef {
+                    file: file.clone(),
+                    dependency: import.clone(),
+                    line_number: dependency.line_number,
+                };
+                match target {
+                    Target::File(path) => {
+                        let to = path.to_string_lossy().to_string();
+                        if to == file || !allowed(&to) {
+                            continue;
+                        }
+                        let depth = graph.nodes[node].depth + 1;
+                        if let Some(added) = add_node(&mut graph, path, depth) {
+                            queue.push_back(added);
+                        }
+                        if edges.insert((file.clone(), to.clone())) {
+                            graph.edges.push(GraphEdge {
+                                from: file.clone(),
+                                to,
+                                dependency: import.clone(),
+                                line_number: dependency.line_number,
+                            });
+                        }
+                    }
+                    Target::External if refs.insert((file.clone(), import.clone())) => {
+                        graph.external.push(reference())
+                    }
+                    Target::Unresolved if refs.insert((file.clone(), import.clone())) => {
+                        graph.unresolved.push(reference())
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    graph
+}
+
+/// Where one import points. Rust `use` lists give one target per path.
+fn resolve_dependency(file: &Path, dependency: &Dependency) -> Vec<(String, Target)> {
+    let import = dependency.dependency.as_str();
+    match file.extension().and_then(|e| e.to_str()).unwrap_or("") {
+        "rs" if dependency.import_type == "mod" => {
+            let target = child_module(file, import).map_or(Target::Unresolved, Target::File);
+            vec![(import.to_string(), target)]
+        }
+        "rs" => expand_use_tree(import)
+            .into_iter()
+            .map(|path| {
+                let target = resolve_rust(file, &path);
+                (path, target)
+            })
+            .collect(),
+        "py" => vec![(import.to_string(), resolve_python(file, import))],
+        _ => vec![(import.to_string(), resolve_relative(file, import))],
+    }
+}
+
+/// JS/TS and anything else importing by relative path
+fn resolve_relative(file: &Path, import: &str) -> Target {
+    if !import.starts_with('.') && !import.starts_with('/') {
+        return Target::External;
+    }
+    let base = normalize(&file.parent().unwrap_or(Path::new("")).join(import));
+    if base.is_file() {
+        return Target::File(base);
+    }
+
+    let extensions = [
+        "js", "jsx", "ts", "tsx", "mjs", "cjs", "css", "scss", "json",
+    ];
+    extensions
+        .iter()
+        .flat_map(|ext| {
+            [
+                PathBuf::from(format!("{}.{}", base.display(), ext)),
+                base.join(format!("index.{}", ext)),
+            ]
+        })
+        .find(|candidate| candidate.is_file())
+        .map_or(Target::Unresolved, Target::File)
+}
+
+/// `from .a import b` is relative to the file's package; absolute imports
+/// are looked up from its directory up to the project root and are
+/// external when no file matches
+fn resolve_python(file: &Path, import: &str) -> Target {
+    let name = import.trim_start_matches('.');
+    let dots = import.len() - name.len();
+    let dir = file.parent().unwrap_or(Path::new(""));
+
+    if dots > 0 {
+        let package = dir.ancestors().nth(dots - 1).unwrap_or(dir);
+        return python_module(package, name).map_or(Target::Unresolved, Target::File);
+    }
+
+    for ancestor in dir.ancestors() {
+        if let Some(found) = python_module(ancestor, name) {
+            return Target::File(found);
+        }
+        let markers = [".git", "pyproject.toml", "setup.py"];
+        if markers.iter().any(|marker| ancestor.join(marker).exists()) {
+            break;
+        }
+    }
+    Target::External
+}
+
+/// `a.b` as `a/b.py` or `a/b/__init__.py` under `dir`; an empty name is
+/// the package `dir` itself
+fn python_module(dir: &Path, name: &str) -> Option<PathBuf> {
+    let base = name
+        .split('.')
+        .filter(|part| !part.is_empty())
+        .fold(dir.to_path_buf(), |path, part| path.join(part));
+    let mut candidates = vec![base.join("__init__.py")];
+    if !name.is_empty() {
+        candidates.insert(0, PathBuf::from(format!("{}.py", base.display())));
+    }
+    candidates.into_iter().find(|candidate| candidate.is_file())
+}
+
+/// `crate::`, `self::` and `super::` paths resolve to the deepest module file
+/// they name; anything else comes from another crate
+fn resolve_rust(file: &Path, path: &str) -> Target {
+    let segments: Vec<&str> = path.split("::").map(str::trim).collect();
+    let (module, rest) = match segments.first() {
+        Some(&"crate") => (crate_root(file), &segments[1..]),
+        Some(&"self") => (Some(file.to_path_buf()), &segments[1..]),
+        Some(&"super") => {
+            let supers = segments.iter().take_while(|s| **s == "super").count();
+            let mut module = Some(file.to_path_buf());
+            for _ in 0..supers {
+                module = module.and_then(|m| parent_module(&m));
+            }
+            (module, &segments[supers..])
+        }
+        _ => return Target::External,
+    };
+    let Some(mut module) = module else {
+        return Target::Unresolved;
+    };
+
+    // Later segments may be items inside the last module file
+    for segment in rest {
+        match child_module(&module, segment) {
+            Some(child) => module = child,
+            None => break,
+        }
+    }
+    Target::File(module)
+}
+
+/// `a::{b, c::d as e, self}` as `a::b`, `a::c::d` and `a`
+fn expand_use_tree(tree: &str) -> Vec<String> {
+    let tree = tree.trim();
+    let Some(open) = tree.find('{') else {
+        let path = tree.split(" as ").next().unwrap_or(tree).trim();
+        return vec![path.to_string()];
+    };
+
+    let prefix = tree[..open].trim().trim_end_matches("::");
+    let inner = &tree[open + 1..tree.rfind('}').unwrap_or(tree.len())];
+
+    let mut items = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in inner.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&inner[start..]);
+
+    items
+        .into_iter()
+        .filter(|item| !item.trim().is_empty())
+        .flat_map(expand_use_tree)
+        .map(|path| match (prefix, path.as_str()) {
+            (_, "self") => prefix.to_string(),
+            ("", _) => path,
+            _ => format!("{}::{}", prefix, path),
+        })
+        .collect()
+}
+
+/// `src/lib.rs`, or `src/main.rs` for binaries, of the crate holding `file`
+fn crate_root(file: &Path) -> Option<PathBuf> {
+    let manifest_dir = file
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join("Cargo.toml").is_file())?;
+    ["lib.rs", "main.rs"]
+        .iter()
+        .map(|root| manifest_dir.join("src").join(root))
+        .find(|root| root.is_file())
+}
+
+/// The file of submodule `name` declared in the module defined by `module`
+fn child_module(module: &Path, name: &str) -> Option<PathBuf> {
+    let dir = match module.file_stem().and_then(|s| s.to_str()) {
+        Some("lib" | "main" | "mod") | None => module.parent()?.to_path_buf(),
+        Some(stem) => module.parent()?.join(stem),
+    };
+    [
+        dir.join(format!("{}.rs", name)),
+        dir.join(name).join("mod.rs"),
+    ]
+    .into_iter()
+    .find(|candidate| candidate.is_file())
+}
+
+/// The file of the module that declares the one defined by `module`
+fn parent_module(module: &Path) -> Option<PathBuf> {
+    let dir = match module.file_stem().and_then(|s| s.to_str())? {
+        "lib" | "main" => return None,
+        "mod" => module.parent()?.parent()?,
+        _ => module.parent()?,
+    };
+    [dir.join("mod.rs"), dir.join("lib.rs"), dir.join("main.rs")]
+        .into_iter()
+        .chain(
+            dir.file_name()
+                .map(|name| dir.with_file_name(format!("{}.rs", name.to_string_lossy()))),
+        )
+        .find(|candidate| candidate.is_file())
+}
+
+/// Remove `.` and `..` without touching the filesystem, so one file always
+/// has one node
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_graph_follows_imports_through_cycles() {
+        let dir = std::env::temp_dir().join(format!("filesurfer_graph_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("web").join("lib")).unwrap();
+        fs::create_dir_all(dir.join("src").join("git")).unwrap();
+
+        // app -> lib (index) -> util -> app, plus a package and a typo
+        let web = dir.join("web");
+        fs::write(web.join("app.js"), "import { x } from './lib';\n").unwrap();
+        fs::write(
+            web.join("lib").join("index.js"),
+            "import React from 'react';\nimport { y } from '../util';\nimport z from './missing';\n",
+        )
+        .unwrap();
+        fs::write(web.join("util.ts"), "export * from './app.js';\n").unwrap();
+
+        fs::write(dir.join("Cargo.toml"), "").unwrap();
+        let src = dir.join("src");
+        fs::write(
+            src.join("lib.rs"),
+            "mod git;\nuse crate::git::{blame::Blame, self};\nuse std::fs;\n",
+        )
+        .unwrap();
+        fs::write(src.join("git.rs"), "mod blame;\n").unwrap();
+        fs::write(
+            src.join("git").join("blame.rs"),
+            "use super::super::Repo;\n",
+        )
+        .unwrap();
+
+        let path = |p: &Path| p.to_string_lossy().to_string();
+        let summary = |graph: &ImportGraph| -> Vec<(String, usize, bool)> {
+            graph
+                .nodes
+                .iter()
+                .map(|n| {
+                    let relative = Path::new(&n.path).strip_prefix(&dir).unwrap();
+                    (
+                        relative.to_string_lossy().replace('\\', "/"),
+                        n.depth,
+                        n.expanded,
+                    )
+                })
+                .collect()
+        };
+
+        let shallow = build_graph(&[path(&web.join("app.js"))], 1, |_| true);
+        assert_eq!(
+            summary(&shallow),
+            vec![
+                ("web/app.js".to_string(), 0, true),
+                ("web/lib/index.js".to_string(), 1, false),
+            ]
+        );
+
+        let full = build_graph(&[path(&web.join("app.js"))], 5, |_| true);
+        assert_eq!(full.nodes.len(), 3);
+        assert_eq!(full.edges.len(), 3);
+        assert_eq!(full.edges[2].to, path(&web.join("app.js")));
+        assert_eq!(full.external[0].dependency, "react");
+        assert_eq!(full.unresolved[0].dependency, "./missing");
+
+        let outside = build_graph(&[path(&web.join("app.js"))], 5, |p| !p.contains("util"));
+        assert_eq!(outside.nodes.len(), 2);
+
+        let rust = build_graph(&[path(&src.join("lib.rs"))], 5, |_| true);
+        assert_eq!(
+            summary(&rust),
+            vec![
+                ("src/lib.rs".to_string(), 0, true),
+                ("src/git.rs".to_string(), 1, true),
+                ("src/git/blame.rs".to_string(), 1, true),
+            ]
+        );
+        // blame.rs reaches back to the crate root
+        assert!(rust.edges.iter().any(|e| e.to == path(&src.join("lib.rs"))));
+        assert_eq!(rust.external[0].dependency, "std::fs");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
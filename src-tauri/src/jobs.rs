@@ -0,0 +1,196 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::AppError;
+
+// Minimum delay between two progress events for the same job
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress of a background job, emitted as a `job-progress` event
+#[derive(Debug, Serialize, Clone)]
+pub struct JobProgress {
+    pub job_id: String,
+    pub files_visited: u64,
+    pub bytes_read: u64,
+    pub current_path: String,
+}
+
+/// Outcome of a background job, emitted as a `job-finished` event
+#[derive(Debug, Serialize, Clone)]
+pub struct JobFinished<T: Serialize + Clone> {
+    pub job_id: String,
+    pub result: Option<T>,
+    pub error: Option<AppError>,
+    pub cancelled: bool,
+}
+
+type ProgressCallback = Box<dyn Fn(JobProgress) + Send + Sync>;
+
+/// Shared state of a running job.
+///
+/// Long operations call [`JobContext::record`] for every file they touch and
+/// stop as soon as [`JobContext::is_cancelled`] returns true.
+pub struct JobContext {
+    id: String,
+    cancelled: AtomicBool,
+    files_visited: AtomicU64,
+    bytes_read: AtomicU64,
+    last_report: Mutex<Instant>,
+    on_progress: Option<ProgressCallback>,
+}
+
+impl JobContext {
+    pub fn new(id: &str, on_progress: ProgressCallback) -> Self {
+        Self {
+            id: id.to_string(),
+            cancelled: AtomicBool::new(false),
+            files_visited: AtomicU64::new(0),
+            bytes_read: AtomicU64::new(0),
+            last_report: Mutex::new(Instant::now()),
+            on_progress: Some(on_progress),
+        }
+    }
+
+    /// A context that never reports and is never cancelled, for synchronous callers
+    pub fn detached() -> Self {
+        Self {
+            id: String::new(),
+            cancelled: AtomicBool::new(false),
+            files_visited: AtomicU64::new(0),
+            bytes_read: AtomicU64::new(0),
+            last_report: Mutex::new(Instant::now()),
+            on_progress: None,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Count a visited file and report progress, throttled to avoid flooding the UI
+    pub fn record(&self, path: &Path, bytes: u64) {
+        let files_visited = self.files_visited.fetch_add(1, Ordering::Relaxed) + 1;
+        let bytes_read = self.bytes_read.fetch_add(bytes, Ordering::Relaxed) + bytes;
+
+        let Some(on_progress) = &self.on_progress else {
+            return;
+        };
+
+        if let Ok(mut last_report) = self.last_report.try_lock() {
+            if last_report.elapsed() < PROGRESS_INTERVAL {
+                return;
+            }
+            *last_report = Instant::now();
+        } else {
+            return;
+        }
+
+        on_progress(JobProgress {
+            job_id: self.id.clone(),
+            files_visited,
+            bytes_read,
+            current_path: path.to_string_lossy().to_string(),
+        });
+    }
+}
+
+/// Registry of running jobs, managed as Tauri state
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<String, Arc<JobContext>>>,
+}
+
+impl JobRegistry {
+    pub fn start(
+        &self,
+        job_id: &str,
+        on_progress: ProgressCallback,
+    ) -> Result<Arc<JobContext>, AppError> {
+        let mut jobs = self
+            .jobs
+            .lock()
+            .map_err(|_| AppError::internal("Job registry is unavailable"))?;
+
+        if jobs.contains_key(job_id) {
+            return Err(AppError::invalid(format!(
+                "Job {} is already running",
+                job_id
+            )));
+        }
+
+        let context = Arc::new(JobContext::new(job_id, on_progress));
+        jobs.insert(job_id.to_string(), context.clone());
+        Ok(context)
+    }
+
+    /// Request cancellation of a running job
+    pub fn cancel(&self, job_id: &str) -> Result<(), AppError> {
+        let jobs = self
+            .jobs
+            .lock()
+            .map_err(|_| AppError::internal("Job registry is unavailable"))?;
+
+        match jobs.get(job_id) {
+            Some(context) => {
+                context.cancel();
+                Ok(())
+            }
+            None => Err(AppError::not_found(format!("No job {} is running", job_id))),
+        }
+    }
+
+    pub fn finish(&self, job_id: &str) {
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.remove(job_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::file_ops::scan_directory_with;
+
+    #[test]
+    fn test_cancel_stops_running_job() {
+        let jobs = JobRegistry::default();
+        let job = jobs.start("scan", Box::new(|_| {})).unwrap();
+
+        jobs.cancel("scan").unwrap();
+        assert!(job.is_cancelled());
+
+        let dir = std::env::temp_dir().to_string_lossy().to_string();
+        let error = scan_directory_with(&dir, Vec::new(), &job).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Cancelled);
+    }
+
+    #[test]
+    fn test_registry_tracks_running_jobs() {
+        let jobs = JobRegistry::default();
+        assert_eq!(
+            jobs.cancel("missing").unwrap_err().kind,
+            ErrorKind::NotFound
+        );
+
+        jobs.start("scan", Box::new(|_| {})).unwrap();
+        let duplicate = jobs.start("scan", Box::new(|_| {})).err().unwrap();
+        assert_eq!(duplicate.kind, ErrorKind::InvalidInput);
+
+        jobs.finish("scan");
+        assert!(jobs.cancel("scan").is_err());
+        assert!(jobs.start("scan", Box::new(|_| {})).is_ok());
+    }
+}
@@ -1,142 +1,525 @@
+mod chunking;
+mod cli;
 mod code_analysis;
 mod config;
+mod error;
 mod file_ops;
+mod git;
+mod http_api;
+mod ignore_rules;
+mod import_graph;
+mod jobs;
+mod mcp;
+mod packing;
+mod path_policy;
+mod project_tree;
+mod read_pipeline;
+mod secrets;
+mod skeleton;
+mod slices;
+mod syntax;
 mod token_counter;
+mod tokenizer;
+mod watcher;
 
+use chunking::{chunk_selection, ChunkRequest, ChunkResult};
 use code_analysis::{
-    detect_dependencies, detect_project_type, extract_functions, extract_todos, filter_comments,
-    resolve_imports, Dependency, Function, ProjectType, TodoItem,
+    detect_dependencies, detect_project_type, extract_functions, extract_outline, extract_todos,
+    filter_comments, resolve_imports, Dependency, Function, ProjectType, Symbol, TodoItem,
 };
 use config::{
     add_recent_path, add_to_clipboard_history, clear_clipboard_history, load_clipboard_history,
-    load_config, load_workspace_state, save_config, save_workspace_state, AppConfig,
-    ClipboardHistory, WorkspaceState,
+    load_config, load_model_registry, load_workspace_state, save_config, save_model_registry,
+    save_workspace_state, AppConfig, ClipboardHistory, ModelRegistry, WorkspaceState,
 };
+use error::AppError;
 use file_ops::{
-    copy_to_clipboard, export_to_file, get_git_tracked_files, read_file_contents, scan_directory,
-    CopyRequest, ExportRequest,
+    copy_to_clipboard, explain_ignored, export_to_file, get_git_tracked_files, list_directory,
+    read_file_contents, read_with_findings, scan_directory, scan_directory_with, CopyRequest,
+    ExportRequest,
 };
+use git::{get_changed_files, ChangeSource, ChangedFile};
+use http_api::{HttpApi, HttpApiInfo};
+use ignore_rules::IgnoreMatch;
+use import_graph::{build_graph, ImportGraph};
+use jobs::{JobContext, JobFinished, JobRegistry};
+use packing::{pack_selection, PackRequest, PackResult};
+use path_policy::PathPolicy;
+use secrets::use_configured_scan;
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
 use token_counter::{estimate_tokens, TokenEstimate};
+use watcher::WorkspaceWatcher;
 
+/// Make `path` the workspace root that every other command is confined to
 #[tauri::command]
-fn scan_dir(path: String, custom_patterns: Vec<String>) -> Result<Vec<file_ops::FileNode>, String> {
+fn open_workspace(policy: State<'_, PathPolicy>, path: String) -> Result<(), AppError> {
+    policy.set_roots(&[path])?;
+    policy.set_allowlist(&load_config()?.allowed_paths)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn scan_dir(
+    policy: State<'_, PathPolicy>,
+    path: String,
+    custom_patterns: Vec<String>,
+) -> Result<Vec<file_ops::FileNode>, AppError> {
+    policy.check(&path)?;
     scan_directory(&path, custom_patterns)
 }
 
 #[tauri::command]
-fn read_files(request: CopyRequest) -> Result<String, String> {
+fn scan_dir_lazy(
+    policy: State<'_, PathPolicy>,
+    path: String,
+    custom_patterns: Vec<String>,
+) -> Result<Vec<file_ops::FileNode>, AppError> {
+    policy.check(&path)?;
+    list_directory(&path, &path, &custom_patterns)
+}
+
+#[tauri::command]
+fn expand_dir(
+    policy: State<'_, PathPolicy>,
+    root: String,
+    path: String,
+    custom_patterns: Vec<String>,
+) -> Result<Vec<file_ops::FileNode>, AppError> {
+    policy.check(&root)?;
+    policy.check(&path)?;
+    list_directory(&root, &path, &custom_patterns)
+}
+
+#[tauri::command]
+fn explain_ignore(
+    policy: State<'_, PathPolicy>,
+    root: String,
+    path: String,
+    custom_patterns: Vec<String>,
+) -> Result<Option<IgnoreMatch>, AppError> {
+    policy.check(&root)?;
+    policy.check(&path)?;
+    explain_ignored(&root, &path, &custom_patterns)
+}
+
+#[tauri::command]
+fn read_files(policy: State<'_, PathPolicy>, mut request: CopyRequest) -> Result<String, AppError> {
+    policy.check_request(&request)?;
+    use_configured_scan(&mut request)?;
     read_file_contents(&request)
 }
 
+/// Register a job whose progress is forwarded to the frontend as `job-progress` events
+fn start_job(
+    app: &AppHandle,
+    jobs: &JobRegistry,
+    job_id: &str,
+) -> Result<Arc<JobContext>, AppError> {
+    let emitter = app.clone();
+    jobs.start(
+        job_id,
+        Box::new(move |progress| {
+            let _ = emitter.emit("job-progress", progress);
+        }),
+    )
+}
+
+/// Unregister a job and emit its outcome as a `job-finished` event
+fn finish_job<T: Serialize + Clone>(
+    app: &AppHandle,
+    job: &JobContext,
+    result: Result<T, AppError>,
+) {
+    app.state::<JobRegistry>().finish(job.id());
+
+    let cancelled = job.is_cancelled();
+    let (result, error) = match result {
+        Ok(value) => (Some(value), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    let _ = app.emit(
+        "job-finished",
+        JobFinished {
+            job_id: job.id().to_string(),
+            result,
+            error,
+            cancelled,
+        },
+    );
+}
+
+#[tauri::command]
+fn start_scan(
+    app: AppHandle,
+    jobs: State<'_, JobRegistry>,
+    policy: State<'_, PathPolicy>,
+    job_id: String,
+    path: String,
+    custom_patterns: Vec<String>,
+) -> Result<(), AppError> {
+    policy.check(&path)?;
+    let job = start_job(&app, &jobs, &job_id)?;
+
+    std::thread::spawn(move || {
+        let result = scan_directory_with(&path, custom_patterns, &job);
+        finish_job(&app, &job, result);
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+fn start_read(
+    app: AppHandle,
+    jobs: State<'_, JobRegistry>,
+    policy: State<'_, PathPolicy>,
+    job_id: String,
+    mut request: CopyRequest,
+) -> Result<(), AppError> {
+    policy.check_request(&request)?;
+    use_configured_scan(&mut request)?;
+    let job = start_job(&app, &jobs, &job_id)?;
+
+    // The preview gets findings rather than an error; block mode asks before copying
+    std::thread::spawn(move || {
+        let result = read_with_findings(&request, &job);
+        finish_job(&app, &job, result);
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+fn cancel_job(jobs: State<'_, JobRegistry>, job_id: String) -> Result<(), AppError> {
+    jobs.cancel(&job_id)
+}
+
+#[tauri::command]
+fn watch_workspace(
+    app: AppHandle,
+    watcher: State<'_, WorkspaceWatcher>,
+    policy: State<'_, PathPolicy>,
+    root: String,
+    custom_patterns: Vec<String>,
+) -> Result<(), AppError> {
+    policy.check(&root)?;
+    watcher.watch(&root, &custom_patterns, move |changed| {
+        let _ = app.emit("tree-changed", changed);
+    })
+}
+
+#[tauri::command]
+fn unwatch_workspace(watcher: State<'_, WorkspaceWatcher>) -> Result<(), AppError> {
+    watcher.unwatch();
+    Ok(())
+}
+
 #[tauri::command]
-fn copy_clipboard(content: String) -> Result<(), String> {
+fn copy_clipboard(content: String) -> Result<(), AppError> {
     copy_to_clipboard(&content)
 }
 
 #[tauri::command]
-fn get_git_files(repo_path: String) -> Result<Vec<String>, String> {
+fn get_git_files(
+    policy: State<'_, PathPolicy>,
+    repo_path: String,
+) -> Result<Vec<String>, AppError> {
+    policy.check(&repo_path)?;
     get_git_tracked_files(&repo_path)
 }
 
 #[tauri::command]
-fn export_file(request: ExportRequest) -> Result<(), String> {
+fn get_git_changes(
+    policy: State<'_, PathPolicy>,
+    repo_path: String,
+    source: ChangeSource,
+) -> Result<Vec<ChangedFile>, AppError> {
+    policy.check(&repo_path)?;
+    get_changed_files(&repo_path, &source)
+}
+
+#[tauri::command]
+fn export_file(policy: State<'_, PathPolicy>, request: ExportRequest) -> Result<(), AppError> {
+    policy.check(&request.path)?;
     export_to_file(&request)
 }
 
+/// Save the settings, asking the user through a native dialog before any new
+/// allowlist entry is trusted, so the webview alone cannot widen the policy
 #[tauri::command]
-fn save_app_config(config: AppConfig) -> Result<(), String> {
+async fn save_app_config(
+    app: AppHandle,
+    policy: State<'_, PathPolicy>,
+    config: AppConfig,
+) -> Result<(), AppError> {
+    let current = load_config()?.allowed_paths;
+    let added: Vec<&String> = config
+        .allowed_paths
+        .iter()
+        .filter(|path| !path.trim().is_empty() && !current.contains(path))
+        .collect();
+
+    if !added.is_empty() {
+        let message = format!(
+            "Allow FileSurfer to read these paths outside the workspace?\n\n{}",
+            added
+                .iter()
+                .map(|path| path.as_str())
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        let confirmed = app
+            .dialog()
+            .message(message)
+            .title("Allowed paths")
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancel)
+            .blocking_show();
+        if !confirmed {
+            return Err(AppError::cancelled("Allowed paths were not confirmed"));
+        }
+    }
+
+    policy.set_allowlist(&config.allowed_paths)?;
     save_config(&config)
 }
 
 #[tauri::command]
-fn load_app_config() -> Result<AppConfig, String> {
+fn load_app_config() -> Result<AppConfig, AppError> {
     load_config()
 }
 
 #[tauri::command]
-fn add_recent(path: String) -> Result<(), String> {
+fn add_recent(path: String) -> Result<(), AppError> {
     add_recent_path(path)
 }
 
 // v3.0 Commands
 #[tauri::command]
-fn estimate_file_tokens(content: String) -> Result<TokenEstimate, String> {
-    Ok(estimate_tokens(&content))
+fn estimate_file_tokens(content: String) -> Result<TokenEstimate, AppError> {
+    estimate_tokens(&content, &load_model_registry()?.models)
 }
 
 #[tauri::command]
-fn get_dependencies(file_path: String) -> Result<Vec<Dependency>, String> {
+fn load_models() -> Result<ModelRegistry, AppError> {
+    load_model_registry()
+}
+
+#[tauri::command]
+fn save_models(registry: ModelRegistry) -> Result<(), AppError> {
+    save_model_registry(&registry)
+}
+
+#[tauri::command]
+fn pack_files(
+    policy: State<'_, PathPolicy>,
+    mut request: PackRequest,
+) -> Result<PackResult, AppError> {
+    policy.check_request(&request.request)?;
+    use_configured_scan(&mut request.request)?;
+    request.candidates = policy.filter_allowed(request.candidates);
+    pack_selection(&request)
+}
+
+#[tauri::command]
+fn chunk_files(
+    policy: State<'_, PathPolicy>,
+    mut request: ChunkRequest,
+) -> Result<ChunkResult, AppError> {
+    policy.check_request(&request.request)?;
+    use_configured_scan(&mut request.request)?;
+    chunk_selection(&request)
+}
+
+#[tauri::command]
+fn get_dependencies(
+    policy: State<'_, PathPolicy>,
+    file_path: String,
+) -> Result<Vec<Dependency>, AppError> {
+    policy.check(&file_path)?;
     detect_dependencies(&file_path)
 }
 
 #[tauri::command]
-fn get_functions(file_path: String) -> Result<Vec<Function>, String> {
+fn get_functions(
+    policy: State<'_, PathPolicy>,
+    file_path: String,
+) -> Result<Vec<Function>, AppError> {
+    policy.check(&file_path)?;
     extract_functions(&file_path)
 }
 
 #[tauri::command]
-fn get_todos(file_path: String) -> Result<Vec<TodoItem>, String> {
+fn get_outline(policy: State<'_, PathPolicy>, file_path: String) -> Result<Vec<Symbol>, AppError> {
+    policy.check(&file_path)?;
+    extract_outline(&file_path)
+}
+
+#[tauri::command]
+fn get_todos(policy: State<'_, PathPolicy>, file_path: String) -> Result<Vec<TodoItem>, AppError> {
+    policy.check(&file_path)?;
     extract_todos(&file_path)
 }
 
 #[tauri::command]
-fn remove_comments(content: String, extension: String, include: bool) -> Result<String, String> {
+fn remove_comments(content: String, extension: String, include: bool) -> Result<String, AppError> {
     Ok(filter_comments(&content, &extension, include))
 }
 
 #[tauri::command]
-fn get_project_type(dir_path: String) -> Result<ProjectType, String> {
+fn get_project_type(
+    policy: State<'_, PathPolicy>,
+    dir_path: String,
+) -> Result<ProjectType, AppError> {
+    policy.check(&dir_path)?;
     detect_project_type(&dir_path)
 }
 
 #[tauri::command]
-fn resolve_file_imports(file_path: String) -> Result<Vec<String>, String> {
-    resolve_imports(&file_path)
+fn resolve_file_imports(
+    policy: State<'_, PathPolicy>,
+    file_path: String,
+) -> Result<Vec<String>, AppError> {
+    policy.check(&file_path)?;
+    // Relative imports can climb out of the workspace with `../`
+    Ok(policy.filter_allowed(resolve_imports(&file_path)?))
 }
 
+/// Files reachable by imports from `seeds`, up to `max_depth` hops
 #[tauri::command]
-fn save_workspace(state: WorkspaceState) -> Result<(), String> {
+fn get_import_graph(
+    policy: State<'_, PathPolicy>,
+    seeds: Vec<String>,
+    max_depth: usize,
+) -> Result<ImportGraph, AppError> {
+    for seed in &seeds {
+        policy.check(seed)?;
+    }
+    // Relative imports can climb out of the workspace with `../`
+    Ok(build_graph(&seeds, max_depth, |path| {
+        policy.check(path).is_ok()
+    }))
+}
+
+#[tauri::command]
+fn save_workspace(state: WorkspaceState) -> Result<(), AppError> {
     save_workspace_state(&state)
 }
 
 #[tauri::command]
-fn load_workspace(path: String) -> Result<WorkspaceState, String> {
+fn load_workspace(path: String) -> Result<WorkspaceState, AppError> {
     load_workspace_state(&path)
 }
 
 #[tauri::command]
-fn add_clipboard_item(content: String, file_count: usize, format: String) -> Result<(), String> {
+fn add_clipboard_item(content: String, file_count: usize, format: String) -> Result<(), AppError> {
     add_to_clipboard_history(content, file_count, format)
 }
 
 #[tauri::command]
-fn get_clipboard_history() -> Result<ClipboardHistory, String> {
+fn get_clipboard_history() -> Result<ClipboardHistory, AppError> {
     load_clipboard_history()
 }
 
 #[tauri::command]
-fn clear_clipboard() -> Result<(), String> {
+fn clear_clipboard() -> Result<(), AppError> {
     clear_clipboard_history()
 }
 
+#[tauri::command]
+fn start_http_api(app: AppHandle, api: State<'_, HttpApi>) -> Result<HttpApiInfo, AppError> {
+    launch_http_api(&app, &api)
+}
+
+#[tauri::command]
+fn stop_http_api(api: State<'_, HttpApi>) {
+    api.stop();
+}
+
+#[tauri::command]
+fn http_api_status(api: State<'_, HttpApi>) -> Option<HttpApiInfo> {
+    api.info()
+}
+
+/// Start the HTTP API with the configured port, creating its token on first use
+fn launch_http_api(app: &AppHandle, api: &HttpApi) -> Result<HttpApiInfo, AppError> {
+    let mut config = load_config()?;
+    if config.http_api_token.is_empty() {
+        config.http_api_token = uuid::Uuid::new_v4().simple().to_string();
+        save_config(&config)?;
+    }
+
+    let emitter = app.clone();
+    let policy = app.state::<PathPolicy>().inner().clone();
+    api.start(
+        config.http_api_port,
+        &config.http_api_token,
+        policy,
+        move |selection| {
+            let _ = emitter.emit("selection-pushed", selection);
+        },
+    )
+}
+
+/// Run a headless subcommand such as `filesurfer pack`, returning the exit code,
+/// or `None` when the arguments should open the GUI
+pub fn run_cli(args: &[String]) -> Option<i32> {
+    cli::run(args)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .manage(JobRegistry::default())
+        .manage(WorkspaceWatcher::default())
+        .manage(HttpApi::default())
+        .manage(PathPolicy::default())
+        .setup(|app| {
+            if load_config().is_ok_and(|config| config.http_api_enabled) {
+                let handle = app.handle();
+                if let Err(e) = launch_http_api(handle, &handle.state::<HttpApi>()) {
+                    eprintln!("HTTP API not started: {}", e);
+                }
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
+            open_workspace,
             scan_dir,
+            scan_dir_lazy,
+            expand_dir,
+            explain_ignore,
             read_files,
+            start_scan,
+            start_read,
+            cancel_job,
+            watch_workspace,
+            unwatch_workspace,
+            start_http_api,
+            stop_http_api,
+            http_api_status,
             copy_clipboard,
             get_git_files,
+            get_git_changes,
             export_file,
             save_app_config,
             load_app_config,
             add_recent,
             // v3.0 commands
             estimate_file_tokens,
+            load_models,
+            save_models,
+            pack_files,
+            chunk_files,
             get_dependencies,
             get_functions,
+            get_outline,
             get_todos,
             remove_comments,
             get_project_type,
@@ -145,7 +528,8 @@ pub fn run() {
             add_clipboard_item,
             get_clipboard_history,
             clear_clipboard,
-            resolve_file_imports
+            resolve_file_imports,
+            get_import_graph
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -2,5 +2,27 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        attach_parent_console();
+    }
+    if let Some(code) = filesurfer_lib::run_cli(&args) {
+        std::process::exit(code);
+    }
+
     filesurfer_lib::run()
 }
+
+/// Release builds use the GUI subsystem, so reattach to the terminal that
+/// started us for CLI output; does nothing when there is no such console
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
@@ -0,0 +1,376 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use crate::code_analysis::{extract_functions, resolve_imports};
+use crate::config::{load_config, load_model_registry, AppConfig};
+use crate::error::{AppError, ErrorKind};
+use crate::file_ops::{read_file_contents, scan_directory, CopyRequest};
+use crate::path_policy::{PathError, PathPolicy};
+use crate::token_counter::estimate_tokens;
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Model Context Protocol server exposing the workspace tools over stdio.
+///
+/// Every path a tool receives must resolve inside one of `roots`.
+pub struct McpServer {
+    roots: Vec<PathBuf>,
+    policy: PathPolicy,
+}
+
+#[derive(Deserialize)]
+struct TreeArgs {
+    path: Option<String>,
+    #[serde(default)]
+    ignore_patterns: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ReadArgs {
+    files: Vec<String>,
+    format: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PathArgs {
+    path: String,
+}
+
+#[derive(Deserialize)]
+struct TokensArgs {
+    text: Option<String>,
+    #[serde(default)]
+    files: Vec<String>,
+}
+
+impl McpServer {
+    pub fn new(roots: &[String]) -> Result<Self, AppError> {
+        if roots.is_empty() {
+            return Err(AppError::invalid("At least one workspace root is required"));
+        }
+
+        let policy = PathPolicy::new(roots)?;
+        policy.set_allowlist(&load_config().unwrap_or_default().allowed_paths)?;
+
+        Ok(Self {
+            roots: policy.roots(),
+            policy,
+        })
+    }
+
+    /// Answer newline-delimited JSON-RPC messages until `input` closes
+    pub fn serve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> Result<(), AppError> {
+        for line in input.lines() {
+            let line = line.map_err(|e| stdio_error(format!("Failed to read request: {}", e)))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle(&message),
+                Err(e) => Some(error_response(Value::Null, PARSE_ERROR, &e.to_string())),
+            };
+
+            if let Some(response) = response {
+                writeln!(output, "{}", response)
+                    .and_then(|_| output.flush())
+                    .map_err(|e| stdio_error(format!("Failed to write response: {}", e)))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle one message, returning the response for requests and `None` for notifications
+    pub fn handle(&self, message: &Value) -> Option<Value> {
+        let id = message.get("id")?.clone();
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "filesurfer", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "ping" => json!({}),
+            "tools/list" => json!({ "tools": tool_definitions() }),
+            "tools/call" => {
+                let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+                let args = params.get("arguments").cloned().unwrap_or(json!({}));
+                match self.call_tool(name, args) {
+                    Ok(text) => tool_result(&text, false),
+                    Err(ToolError::Params(message)) => {
+                        return Some(error_response(id, INVALID_PARAMS, &message))
+                    }
+                    Err(ToolError::Failed(message)) => tool_result(&message, true),
+                }
+            }
+            _ => {
+                return Some(error_response(
+                    id,
+                    METHOD_NOT_FOUND,
+                    &format!("Method not found: {}", method),
+                ))
+            }
+        };
+
+        Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+    }
+
+    fn call_tool(&self, name: &str, args: Value) -> Result<String, ToolError> {
+        // Read once per call, so settings changed in the app apply without a restart
+        let config = load_config().unwrap_or_default();
+
+        match name {
+            "tree" => {
+                let args: TreeArgs = parse_args(args)?;
+                let dir = match &args.path {
+                    Some(path) => self.allowed(path)?,
+                    None => self.roots[0].clone(),
+                };
+                let mut ignore_patterns = config.custom_ignore_patterns;
+                ignore_patterns.extend(args.ignore_patterns);
+                let nodes = scan_directory(&dir.to_string_lossy(), ignore_patterns)?;
+                to_json(&nodes)
+            }
+            "read" => {
+                let args: ReadArgs = parse_args(args)?;
+                let request = self.copy_request(&args.files, args.format, &config)?;
+                read_file_contents(&CopyRequest {
+                    max_file_size_mb: Some(config.max_file_size_mb),
+                    ..request
+                })
+                .map_err(ToolError::from)
+            }
+            "functions" => {
+                let args: PathArgs = parse_args(args)?;
+                let path = self.allowed(&args.path)?;
+                to_json(&extract_functions(&path.to_string_lossy())?)
+            }
+            "imports" => {
+                let args: PathArgs = parse_args(args)?;
+                let path = self.allowed(&args.path)?;
+                // Imports may point anywhere; only report those inside the workspace
+                let imports = resolve_imports(&path.to_string_lossy())?;
+                to_json(&self.policy.filter_allowed(imports))
+            }
+            "tokens" => {
+                let args: TokensArgs = parse_args(args)?;
+                let text = match args.text {
+                    Some(text) => text,
+                    None if !args.files.is_empty() => {
+                        read_file_contents(&self.copy_request(&args.files, None, &config)?)?
+                    }
+                    None => return Err(ToolError::Params("Pass text or files".to_string())),
+                };
+                to_json(&estimate_tokens(&text, &load_model_registry()?.models)?)
+            }
+            _ => Err(ToolError::Params(format!("Unknown tool: {}", name))),
+        }
+    }
+
+    /// Resolve `path` against the first root and reject anything outside the roots
+    fn allowed(&self, path: &str) -> Result<PathBuf, ToolError> {
+        let joined = self.roots[0].join(path);
+        Ok(self.policy.check(&joined.to_string_lossy())?)
+    }
+
+    fn copy_request(
+        &self,
+        files: &[String],
+        format: Option<String>,
+        config: &AppConfig,
+    ) -> Result<CopyRequest, ToolError> {
+        let files = files
+            .iter()
+            .map(|file| self.allowed(file))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Paths are shown relative to the root holding the first file
+        let base_path = files
+            .first()
+            .and_then(|file| self.roots.iter().find(|root| file.starts_with(root)))
+            .unwrap_or(&self.roots[0]);
+
+        Ok(CopyRequest {
+            files: files
+                .iter()
+                .map(|file| file.to_string_lossy().to_string())
+                .collect(),
+            base_path: base_path.to_string_lossy().to_string(),
+            format: format.unwrap_or_else(|| "markdown".to_string()),
+            ignore_patterns: config.custom_ignore_patterns.clone(),
+            // Agents cannot confirm, so block mode refuses the read
+            secret_scan: Some(config.secret_scan.clone()),
+            ..Default::default()
+        })
+    }
+}
+
+enum ToolError {
+    Params(String),
+    Failed(String),
+}
+
+impl From<AppError> for ToolError {
+    fn from(error: AppError) -> Self {
+        ToolError::Failed(error.message)
+    }
+}
+
+impl From<PathError> for ToolError {
+    fn from(error: PathError) -> Self {
+        ToolError::Failed(error.to_string())
+    }
+}
+
+fn stdio_error(message: String) -> AppError {
+    AppError::new(ErrorKind::Io, message)
+}
+
+fn parse_args<T: for<'de> Deserialize<'de>>(args: Value) -> Result<T, ToolError> {
+    serde_json::from_value(args).map_err(|e| ToolError::Params(e.to_string()))
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, ToolError> {
+    serde_json::to_string_pretty(value)
+        .map_err(|e| ToolError::Failed(format!("Failed to serialize to JSON: {}", e)))
+}
+
+fn tool_result(text: &str, is_error: bool) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn tool_definitions() -> Value {
+    let path_schema = |description: &str| {
+        json!({
+            "type": "object",
+            "properties": { "path": { "type": "string", "description": description } },
+            "required": ["path"],
+        })
+    };
+    let files_schema = json!({
+        "type": "array",
+        "items": { "type": "string" },
+        "description": "Paths relative to the first workspace root, or absolute",
+    });
+
+    json!([
+        {
+            "name": "tree",
+            "description": "List the workspace tree, honouring .gitignore and configured ignore patterns",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Directory to list, defaults to the first root" },
+                    "ignore_patterns": { "type": "array", "items": { "type": "string" } },
+                },
+            },
+        },
+        {
+            "name": "read",
+            "description": "Read files formatted as one context bundle",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "files": files_schema,
+                    "format": { "type": "string", "enum": ["markdown", "json", "xml", "diff"] },
+                },
+                "required": ["files"],
+            },
+        },
+        {
+            "name": "functions",
+            "description": "List the functions defined in a file with their line ranges",
+            "inputSchema": path_schema("File to analyse"),
+        },
+        {
+            "name": "imports",
+            "description": "Resolve a file's relative imports to workspace files",
+            "inputSchema": path_schema("File whose imports to resolve"),
+        },
+        {
+            "name": "tokens",
+            "description": "Count tokens for every configured model, for text or a set of files",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string" },
+                    "files": files_schema,
+                },
+            },
+        },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_tools_stay_inside_roots() {
+        let base = std::env::temp_dir().join(format!("filesurfer_mcp_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("workspace");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("lib.rs"), "fn helper() {\n}\n").unwrap();
+        fs::write(base.join("secret.txt"), "hidden").unwrap();
+
+        let server = McpServer::new(&[root.to_string_lossy().to_string()]).unwrap();
+        let input = [
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/call",
+                    "params": { "name": "functions", "arguments": { "path": "lib.rs" } } }),
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "tools/call",
+                    "params": { "name": "read", "arguments": { "files": ["../secret.txt"] } } }),
+        ]
+        .iter()
+        .map(|m| m.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+        let mut output = Vec::new();
+        server.serve(input.as_bytes(), &mut output).unwrap();
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["result"]["protocolVersion"], PROTOCOL_VERSION);
+        assert_eq!(responses[1]["result"]["isError"], false);
+        assert!(responses[1]["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("helper"));
+        assert_eq!(responses[2]["result"]["isError"], true);
+        assert!(responses[2]["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("outside the workspace"));
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
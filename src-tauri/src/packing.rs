@@ -0,0 +1,404 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::code_analysis::resolve_imports;
+use crate::config::{find_model, load_model_registry};
+use crate::error::AppError;
+use crate::file_ops::{
+    format_loaded, format_output, max_file_size, prepare_loaded, project_tree, requested_files,
+    visible_files, CopyRequest,
+};
+use crate::import_graph::normalize;
+use crate::jobs::JobContext;
+use crate::read_pipeline::{get_relative_path, load_files, FileBody, LoadedFile};
+use crate::secrets::ensure_confirmed;
+use crate::token_counter::model_tokenizer;
+use crate::tokenizer::Tokenizer;
+
+/// Why a candidate was considered, from most to least important
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum PackPriority {
+    Selected,
+    Import,
+    SameDirectory,
+    Candidate,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackRequest {
+    /// `files` holds the explicitly selected files
+    pub request: CopyRequest,
+    pub candidates: Vec<String>,
+    pub model: String,
+    pub token_budget: u32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PackedFile {
+    pub path: String,
+    pub relative_path: String,
+    pub priority: PackPriority,
+    pub tokens: u32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DroppedFile {
+    pub path: String,
+    pub relative_path: String,
+    pub priority: PackPriority,
+    pub tokens: Option<u32>,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PackResult {
+    pub model: String,
+    pub token_budget: u32,
+    pub total_tokens: u32,
+    pub files: Vec<PackedFile>,
+    pub dropped: Vec<DroppedFile>,
+    pub content: String,
+}
+
+/// Pick the subset of the selection and candidates that fits in a token budget.
+///
+/// Files are taken in priority order: the explicit selection, then files it
+/// imports, then files sharing a directory with it, then other candidates.
+/// A file that does not fit is skipped so that smaller ones can still be used.
+pub fn pack_selection(pack: &PackRequest) -> Result<PackResult, AppError> {
+    let registry = load_model_registry()?;
+    let tokenizer = model_tokenizer(find_model(&registry, &pack.model)?)?;
+    pack_with(pack, tokenizer.as_ref())
+}
+
+/// `pack_selection` with the tokenizer already chosen
+pub fn pack_with(pack: &PackRequest, tokenizer: &dyn Tokenizer) -> Result<PackResult, AppError> {
+    let request = &pack.request;
+    let base_path = Path::new(&request.base_path);
+    let max_size = max_file_size(request);
+
+    let pool = rank_candidates(&requested_files(request), &pack.candidates);
+    let pool_request = CopyRequest {
+        files: pool.iter().map(|(path, _)| path.clone()).collect(),
+        ..request.clone()
+    };
+    let visible: HashSet<String> = visible_files(&pool_request)?.into_iter().collect();
+
+    let mut dropped = Vec::new();
+    let mut readable = Vec::new();
+
+    for (path, priority) in &pool {
+        if visible.contains(path) {
+            readable.push((path.clone(), *priority));
+        } else {
+            dropped.push(DroppedFile {
+                path: path.clone(),
+                relative_path: get_relative_path(Path::new(path), base_path),
+                priority: *priority,
+                tokens: None,
+                reason: "Hidden by ignore rules".to_string(),
+            });
+        }
+    }
+
+    let paths: Vec<String> = readable.iter().map(|(path, _)| path.clone()).collect();
+    let mut loaded = load_files(&paths, base_path, max_size, &JobContext::detached())?;
+    // Diff and redact before counting so the budget matches what is copied
+    let findings = prepare_loaded(&mut loaded, request)?;
+
+    let mut packed: Vec<(LoadedFile, PackPriority, u32)> = Vec::new();
+    // The project tree comes out of the same budget
+    let tree = project_tree(request)?;
+    let mut used = tree.as_deref().map_or(0, |tree| tokenizer.count(tree));
+
+    // Selected slices turn one file into several entries. Files named only in
+    // `selections` were ranked with `files`, so they keep the selected priority.
+    let priorities: HashMap<PathBuf, PackPriority> = readable
+        .into_iter()
+        .map(|(path, priority)| (normalize(Path::new(&path)), priority))
+        .collect();
+    for file in loaded {
+        let priority = priorities
+            .get(&normalize(&file.path))
+            .copied()
+            .unwrap_or(PackPriority::Selected);
+        let reason = match &file.body {
+            FileBody::Text(_) => None,
+            FileBody::Binary => Some("Binary file".to_string()),
+            FileBody::TooLarge => Some(format!("File too large: {} bytes", file.size)),
+            FileBody::Missing => Some("File not found".to_string()),
+            FileBody::Error(e) => Some(format!("Error reading file: {}", e)),
+        };
+
+        if let Some(reason) = reason {
+            dropped.push(dropped_file(&file, priority, None, reason));
+            continue;
+        }
+
+        // Count the file as it will appear in the output, headers included
+        let block = format_loaded(std::slice::from_ref(&file), &request.format, max_size)?;
+        let tokens = tokenizer.count(&block);
+
+        if used + tokens > pack.token_budget {
+            let reason = format!(
+                "Exceeds remaining budget: needs {} tokens, {} left",
+                tokens,
+                pack.token_budget.saturating_sub(used)
+            );
+            dropped.push(dropped_file(&file, priority, Some(tokens), reason));
+            continue;
+        }
+
+        used += tokens;
+        packed.push((file, priority, tokens));
+    }
+
+    let files: Vec<LoadedFile> = packed.iter().map(|(file, _, _)| file.clone()).collect();
+    if let Some(scan) = &request.secret_scan {
+        let packed_findings: Vec<_> = findings
+            .into_iter()
+            .filter(|finding| files.iter().any(|file| file.relative_path == finding.path))
+            .collect();
+        ensure_confirmed(&packed_findings, scan, request.secrets_confirmed)?;
+    }
+    let content = format_output(&files, tree.as_deref(), &request.format, max_size)?;
+    let total_tokens = tokenizer.count(&content);
+
+    Ok(PackResult {
+        model: pack.model.clone(),
+        token_budget: pack.token_budget,
+        total_tokens,
+        files: packed
+            .into_iter()
+            .map(|(file, priority, tokens)| PackedFile {
+                path: file.path.to_string_lossy().to_string(),
+                relative_path: file.relative_path,
+                priority,
+                tokens,
+            })
+            .collect(),
+        dropped,
+        content,
+    })
+}
+
+fn dropped_file(
+    file: &LoadedFile,
+    priority: PackPriority,
+    tokens: Option<u32>,
+    reason: String,
+) -> DroppedFile {
+    DroppedFile {
+        path: file.path.to_string_lossy().to_string(),
+        relative_path: file.relative_path.clone(),
+        priority,
+        tokens,
+        reason,
+    }
+}
+
+/// Assign a priority to every distinct file and sort by it, keeping the
+/// original order within a priority. Paths are compared after removing `.`
+/// and `..`, so differently spelled paths to one file still match.
+fn rank_candidates(selected: &[String], candidates: &[String]) -> Vec<(String, PackPriority)> {
+    let imports: HashSet<PathBuf> = selected
+        .iter()
+        .filter_map(|file| resolve_imports(file).ok())
+        .flatten()
+        .map(|import| normalize(Path::new(&import)))
+        .collect();
+
+    let selected_dirs: HashSet<PathBuf> = selected
+        .iter()
+        .filter_map(|file| normalize(Path::new(file)).parent().map(Path::to_path_buf))
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut ranked = Vec::new();
+
+    for file in selected {
+        if seen.insert(normalize(Path::new(file))) {
+            ranked.push((file.clone(), PackPriority::Selected));
+        }
+    }
+
+    for file in candidates {
+        let path = normalize(Path::new(file));
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+
+        let priority = if imports.contains(&path) {
+            PackPriority::Import
+        } else if path.parent().is_some_and(|dir| selected_dirs.contains(dir)) {
+            PackPriority::SameDirectory
+        } else {
+            PackPriority::Candidate
+        };
+
+        ranked.push((file.clone(), priority));
+    }
+
+    ranked.sort_by_key(|(_, priority)| *priority);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_tree::TreeOptions;
+    use crate::slices::{Selection, SelectionTarget};
+    use crate::tokenizer::bpe_tokenizer;
+    use std::fs;
+
+    #[test]
+    fn test_tree_larger_than_budget_drops_files() {
+        let dir = std::env::temp_dir().join(format!("filesurfer_pack_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for i in 0..20 {
+            fs::write(dir.join(format!("module_{}.rs", i)), "fn main() {}\n").unwrap();
+        }
+        let file = dir.join("module_0.rs").to_string_lossy().to_string();
+
+        let pack = PackRequest {
+            request: CopyRequest {
+                files: vec![file.clone()],
+                base_path: dir.to_string_lossy().to_string(),
+                format: "markdown".to_string(),
+                tree: Some(TreeOptions {
+                    max_depth: None,
+                    show_sizes: false,
+                }),
+                ..Default::default()
+            },
+            candidates: Vec::new(),
+            model: "gpt-4o".to_string(),
+            token_budget: 10,
+        };
+        let tokenizer = bpe_tokenizer("cl100k_base").unwrap();
+        let result = pack_with(&pack, &tokenizer).unwrap();
+
+        assert!(result.files.is_empty());
+        assert_eq!(result.dropped.len(), 1);
+        assert_eq!(result.dropped[0].path, file);
+        assert!(result.dropped[0].reason.ends_with(", 0 left"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+    #[test]
+    fn test_pack_ranks_candidates_and_reports_skips() {
+        let dir = std::env::temp_dir().join(format!("filesurfer_pack_rank_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("other")).unwrap();
+        fs::write(
+            dir.join("main.js"),
+            "import { util } from './util.js';\nutil();\n",
+        )
+        .unwrap();
+        fs::write(dir.join("util.js"), "export function util() {}\n").unwrap();
+        fs::write(dir.join("sibling.js"), "sibling();\n").unwrap();
+        fs::write(dir.join("big.js"), "big();\n".repeat(400)).unwrap();
+        fs::write(dir.join("image.bin"), [0u8, 1, 2, 3]).unwrap();
+        fs::write(dir.join("debug.log"), "log\n").unwrap();
+        fs::write(dir.join("other").join("far.js"), "far();\n").unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+        let pack = PackRequest {
+            request: CopyRequest {
+                files: vec![path("main.js")],
+                base_path: dir.to_string_lossy().to_string(),
+                format: "markdown".to_string(),
+                ignore_patterns: vec!["*.log".to_string()],
+                ..Default::default()
+            },
+            candidates: vec![
+                path("other/far.js"),
+                path("big.js"),
+                // Spelled differently from the resolved import
+                path("other/../util.js"),
+                path("sibling.js"),
+                path("image.bin"),
+                path("missing.js"),
+                path("debug.log"),
+            ],
+            model: "gpt-4o".to_string(),
+            token_budget: 300,
+        };
+        let tokenizer = bpe_tokenizer("cl100k_base").unwrap();
+        let result = pack_with(&pack, &tokenizer).unwrap();
+
+        let packed: Vec<(&str, PackPriority)> = result
+            .files
+            .iter()
+            .map(|file| (file.relative_path.as_str(), file.priority))
+            .collect();
+        assert_eq!(
+            packed,
+            vec![
+                ("main.js", PackPriority::Selected),
+                ("other/../util.js", PackPriority::Import),
+                ("sibling.js", PackPriority::SameDirectory),
+                ("other/far.js", PackPriority::Candidate),
+            ]
+        );
+        assert!(result.total_tokens <= 300);
+
+        let dropped: Vec<(&str, &str)> = result
+            .dropped
+            .iter()
+            .map(|file| {
+                let reason = file.reason.split(':').next().unwrap();
+                (file.relative_path.as_str(), reason)
+            })
+            .collect();
+        assert_eq!(
+            dropped,
+            vec![
+                ("debug.log", "Hidden by ignore rules"),
+                ("big.js", "Exceeds remaining budget"),
+                ("image.bin", "Binary file"),
+                ("missing.js", "File not found"),
+            ]
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+    #[test]
+    fn test_selection_only_files_keep_priority() {
+        let dir =
+            std::env::temp_dir().join(format!("filesurfer_pack_slice_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let body = "fn helper() {\n    println!(\"a fairly long line of output\");\n}\n";
+        fs::write(dir.join("picked.rs"), body.repeat(4)).unwrap();
+        fs::write(dir.join("candidate.rs"), body).unwrap();
+        let picked = dir.join("picked.rs").to_string_lossy().to_string();
+
+        let pack = PackRequest {
+            request: CopyRequest {
+                base_path: dir.to_string_lossy().to_string(),
+                format: "markdown".to_string(),
+                selections: vec![Selection {
+                    file: picked.clone(),
+                    target: SelectionTarget::Lines { start: 1, end: 3 },
+                }],
+                ..Default::default()
+            },
+            candidates: vec![dir.join("candidate.rs").to_string_lossy().to_string()],
+            model: "gpt-4o".to_string(),
+            token_budget: 40,
+        };
+        let tokenizer = bpe_tokenizer("cl100k_base").unwrap();
+        let result = pack_with(&pack, &tokenizer).unwrap();
+
+        assert_eq!(result.files.len(), 1);
+        assert_eq!(result.files[0].path, picked);
+        assert_eq!(result.files[0].priority, PackPriority::Selected);
+        assert_eq!(result.dropped[0].priority, PackPriority::SameDirectory);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
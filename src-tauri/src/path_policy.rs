@@ -0,0 +1,346 @@
+use serde::Serialize;
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::file_ops::{requested_files, CopyRequest};
+
+/// Why a path was refused
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PathError {
+    NoWorkspace { path: String },
+    OutsideWorkspace { path: String },
+    OutsideBasePath { path: String, base_path: String },
+    Unresolvable { path: String, message: String },
+    TooBroad { path: String },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::NoWorkspace { path } => {
+                write!(f, "No workspace is open, refusing {}", path)
+            }
+            PathError::OutsideWorkspace { path } => {
+                write!(f, "Path is outside the workspace: {}", path)
+            }
+            PathError::OutsideBasePath { path, base_path } => {
+                write!(f, "Path {} is outside the base path {}", path, base_path)
+            }
+            PathError::Unresolvable { path, message } => {
+                write!(f, "Cannot resolve {}: {}", path, message)
+            }
+            PathError::TooBroad { path } => {
+                write!(f, "Refusing to allow a filesystem or home root: {}", path)
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct AllowedPaths {
+    roots: Vec<PathBuf>,
+    allowlist: Vec<PathBuf>,
+}
+
+/// The single place that decides which paths commands may touch.
+///
+/// Paths are canonicalized, so symlinks and `..` cannot lead out of the
+/// workspace roots or the configured allowlist. Cloning shares the same roots.
+#[derive(Clone, Default)]
+pub struct PathPolicy {
+    allowed: Arc<RwLock<AllowedPaths>>,
+}
+
+impl PathPolicy {
+    pub fn new(roots: &[String]) -> Result<Self, PathError> {
+        let policy = Self::default();
+        policy.set_roots(roots)?;
+        Ok(policy)
+    }
+
+    /// Replace the workspace roots, e.g. when a folder is opened.
+    ///
+    /// Filesystem roots and the home directory or its ancestors are refused.
+    pub fn set_roots(&self, roots: &[String]) -> Result<(), PathError> {
+        let home = home_dir();
+        let roots = roots
+            .iter()
+            .map(|root| {
+                let dir = canonical_dir(root)?;
+                check_narrow(root, &dir, home.as_deref())?;
+                Ok(dir)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Ok(mut allowed) = self.allowed.write() {
+            allowed.roots = roots;
+        }
+        Ok(())
+    }
+
+    /// Replace the extra files and directories allowed outside the roots.
+    ///
+    /// Entries that do not exist are skipped; filesystem and home roots are
+    /// refused like in `set_roots`.
+    pub fn set_allowlist(&self, paths: &[String]) -> Result<(), PathError> {
+        let home = home_dir();
+        let mut allowlist = Vec::new();
+        for path in paths {
+            let Ok(resolved) = Path::new(path).canonicalize() else {
+                continue;
+            };
+            check_narrow(path, &resolved, home.as_deref())?;
+            allowlist.push(resolved);
+        }
+
+        if let Ok(mut allowed) = self.allowed.write() {
+            allowed.allowlist = allowlist;
+        }
+        Ok(())
+    }
+
+    pub fn roots(&self) -> Vec<PathBuf> {
+        self.allowed
+            .read()
+            .map(|allowed| allowed.roots.clone())
+            .unwrap_or_default()
+    }
+
+    /// Resolve `path` and make sure it is inside a root or on the allowlist
+    pub fn check(&self, path: &str) -> Result<PathBuf, PathError> {
+        let resolved = resolve(path)?;
+        let allowed = self.allowed.read().map_err(|_| PathError::NoWorkspace {
+            path: path.to_string(),
+        })?;
+
+        if allowed.roots.is_empty() && allowed.allowlist.is_empty() {
+            return Err(PathError::NoWorkspace {
+                path: path.to_string(),
+            });
+        }
+
+        if allowed
+            .roots
+            .iter()
+            .chain(&allowed.allowlist)
+            .any(|prefix| resolved.starts_with(prefix))
+        {
+            Ok(resolved)
+        } else {
+            Err(PathError::OutsideWorkspace {
+                path: path.to_string(),
+            })
+        }
+    }
+
+    /// Check the base path of a copy request and that every file it reads,
+    /// selections included, is inside it
+    pub fn check_request(&self, request: &CopyRequest) -> Result<(), PathError> {
+        let base = self.check(&request.base_path)?;
+
+        for file in requested_files(request) {
+            if !resolve(&file)?.starts_with(&base) {
+                return Err(PathError::OutsideBasePath {
+                    path: file,
+                    base_path: request.base_path.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Keep only the paths that pass `check`
+    pub fn filter_allowed(&self, paths: Vec<String>) -> Vec<String> {
+        paths
+            .into_iter()
+            .filter(|path| self.check(path).is_ok())
+            .collect()
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    dirs::home_dir().and_then(|home| home.canonicalize().ok())
+}
+
+/// Refuse a resolved path that would expose a whole filesystem or home
+/// directory, e.g. `/`, `C:\`, `/home` or `~`
+fn check_narrow(path: &str, resolved: &Path, home: Option<&Path>) -> Result<(), PathError> {
+    if resolved.parent().is_none() || home.is_some_and(|home| home.starts_with(resolved)) {
+        return Err(PathError::TooBroad {
+            path: path.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn canonical_dir(path: &str) -> Result<PathBuf, PathError> {
+    let canonical = Path::new(path)
+        .canonicalize()
+        .map_err(|e| PathError::Unresolvable {
+            path: path.to_string(),
+            message: e.to_string(),
+        })?;
+
+    if !canonical.is_dir() {
+        return Err(PathError::Unresolvable {
+            path: path.to_string(),
+            message: "Not a directory".to_string(),
+        });
+    }
+    Ok(canonical)
+}
+
+/// Canonicalize an absolute path that may not exist yet.
+///
+/// Missing trailing components are appended to their nearest existing
+/// ancestor; a missing component followed by `..` is refused.
+pub fn resolve(path: &str) -> Result<PathBuf, PathError> {
+    let unresolvable = |message: String| PathError::Unresolvable {
+        path: path.to_string(),
+        message,
+    };
+
+    let mut existing = Path::new(path);
+    if !existing.is_absolute() {
+        return Err(unresolvable("Path must be absolute".to_string()));
+    }
+
+    let mut missing = Vec::new();
+    loop {
+        match existing.canonicalize() {
+            Ok(mut resolved) => {
+                resolved.extend(missing.iter().rev());
+                return Ok(resolved);
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let (Some(name), Some(parent)) = (existing.file_name(), existing.parent()) else {
+                    return Err(unresolvable(e.to_string()));
+                };
+                missing.push(name);
+                existing = parent;
+            }
+            Err(e) => return Err(unresolvable(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slices::{Selection, SelectionTarget};
+    use std::fs;
+
+    #[test]
+    fn test_policy_refuses_escapes() {
+        let base = std::env::temp_dir().join(format!("filesurfer_policy_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("workspace");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src").join("lib.rs"), "").unwrap();
+        fs::write(base.join("secret.txt"), "").unwrap();
+        let path = |p: &Path| p.to_string_lossy().to_string();
+
+        let policy = PathPolicy::default();
+        assert!(matches!(
+            policy.check(&path(&root.join("src"))),
+            Err(PathError::NoWorkspace { .. })
+        ));
+
+        policy.set_roots(&[path(&root)]).unwrap();
+        assert!(policy
+            .check(&path(&root.join("src").join("lib.rs")))
+            .is_ok());
+        // Files that do not exist yet, such as export targets, are resolved too
+        assert!(policy
+            .check(&path(&root.join("out").join("ctx.md")))
+            .is_ok());
+        assert!(matches!(
+            policy.check(&path(&root.join("..").join("secret.txt"))),
+            Err(PathError::OutsideWorkspace { .. })
+        ));
+        assert!(policy
+            .check(&path(
+                &root
+                    .join("missing")
+                    .join("..")
+                    .join("..")
+                    .join("secret.txt")
+            ))
+            .is_err());
+        assert!(policy.check("relative/path").is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(base.join("secret.txt"), root.join("link.txt")).unwrap();
+            assert!(policy.check(&path(&root.join("link.txt"))).is_err());
+        }
+
+        policy
+            .set_allowlist(&[path(&base.join("secret.txt"))])
+            .unwrap();
+        assert!(policy.check(&path(&base.join("secret.txt"))).is_ok());
+
+        let request = CopyRequest {
+            files: vec![
+                path(&root.join("src").join("lib.rs")),
+                path(&base.join("secret.txt")),
+            ],
+            base_path: path(&root.join("src")),
+            format: "markdown".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            policy.check_request(&request),
+            Err(PathError::OutsideBasePath { .. })
+        ));
+
+        // Selected slices are read like files, so they need the same check
+        let request = CopyRequest {
+            files: vec![path(&root.join("src").join("lib.rs"))],
+            selections: vec![Selection {
+                file: path(&base.join("secret.txt")),
+                target: SelectionTarget::Lines { start: 1, end: 999 },
+            }],
+            ..request
+        };
+        assert!(matches!(
+            policy.check_request(&request),
+            Err(PathError::OutsideBasePath { .. })
+        ));
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn test_policy_refuses_broad_roots() {
+        let base = std::env::temp_dir().join(format!("filesurfer_broad_{}", std::process::id()));
+        let home = base.join("home").join("me");
+        fs::create_dir_all(home.join("project")).unwrap();
+        let base = base.canonicalize().unwrap();
+        let home = home.canonicalize().unwrap();
+
+        let root = Path::new("/").canonicalize().unwrap();
+        assert!(check_narrow("/", &root, None).is_err());
+        assert!(check_narrow("~", &home, Some(&home)).is_err());
+        assert!(check_narrow("/home", home.parent().unwrap(), Some(&home)).is_err());
+        assert!(check_narrow("project", &home.join("project"), Some(&home)).is_ok());
+
+        let policy = PathPolicy::default();
+        let root = root.to_string_lossy().to_string();
+        assert!(matches!(
+            policy.set_roots(std::slice::from_ref(&root)),
+            Err(PathError::TooBroad { .. })
+        ));
+        assert!(matches!(
+            policy.set_allowlist(&[root]),
+            Err(PathError::TooBroad { .. })
+        ));
+        assert!(policy.roots().is_empty());
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
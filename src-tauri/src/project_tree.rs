@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::error::AppError;
+use crate::file_ops::{
+    scan_directory, scan_directory_to_depth, visible_files, CopyRequest, FileNode,
+};
+
+/// Adds an ASCII tree of the project ahead of the copied files
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TreeOptions {
+    /// Directories deeper than this are collapsed; unlimited when absent
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    #[serde(default)]
+    pub show_sizes: bool,
+}
+
+/// The project layout with the files that will be read marked `*`, selections
+/// included, honouring the request's ignore rules like the file tree does.
+///
+/// Directories below `max_depth` are not walked.
+pub fn render_tree(request: &CopyRequest, options: &TreeOptions) -> Result<String, AppError> {
+    let nodes = match options.max_depth {
+        Some(max_depth) => {
+            scan_directory_to_depth(&request.base_path, &request.ignore_patterns, max_depth)?
+        }
+        None => scan_directory(&request.base_path, request.ignore_patterns.clone())?,
+    };
+    let files = visible_files(request)?;
+    let selected: HashSet<&str> = files.iter().map(String::as_str).collect();
+
+    let root_name = Path::new(&request.base_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| request.base_path.clone());
+    let mut output = format!("{}/\n", root_name);
+    render_level(&nodes, "", 1, options, &selected, &mut output);
+    output.push_str("(* = included below)\n");
+
+    Ok(output)
+}
+
+fn render_level(
+    nodes: &[FileNode],
+    prefix: &str,
+    depth: usize,
+    options: &TreeOptions,
+    selected: &HashSet<&str>,
+    output: &mut String,
+) {
+    for (i, node) in nodes.iter().enumerate() {
+        let last = i + 1 == nodes.len();
+        let branch = if last { "└── " } else { "├── " };
+        output.push_str(prefix);
+        output.push_str(branch);
+
+        if !node.is_dir {
+            output.push_str(&node.name);
+            if options.show_sizes {
+                output.push_str(&format!(" ({})", human_size(node.size)));
+            }
+            if selected.contains(node.path.as_str()) {
+                output.push_str(" *");
+            }
+            output.push('\n');
+            continue;
+        }
+
+        output.push_str(&node.name);
+        output.push('/');
+        let children = node.children.as_deref().unwrap_or_default();
+
+        if options.max_depth.is_some_and(|max| depth >= max) && node.child_count > 0 {
+            let marked = selected
+                .iter()
+                .filter(|file| Path::new(file).starts_with(&node.path))
+                .count();
+            output.push_str(&format!(" ... {} entries", node.child_count));
+            if marked > 0 {
+                output.push_str(&format!(", {} included", marked));
+            }
+            output.push('\n');
+            continue;
+        }
+
+        output.push('\n');
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        render_level(
+            children,
+            &child_prefix,
+            depth + 1,
+            options,
+            selected,
+            output,
+        );
+    }
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slices::{Selection, SelectionTarget};
+    use std::fs;
+
+    #[test]
+    fn test_tree_marks_selection_and_collapses_depth() {
+        let dir = std::env::temp_dir().join(format!("filesurfer_tree_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src").join("nested")).unwrap();
+        fs::write(dir.join("src").join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.join("src").join("nested").join("deep.rs"), "").unwrap();
+        fs::write(dir.join("README.md"), vec![b'x'; 2048]).unwrap();
+        fs::write(dir.join("debug.log"), "").unwrap();
+
+        let request = CopyRequest {
+            files: vec![dir
+                .join("src")
+                .join("main.rs")
+                .to_string_lossy()
+                .to_string()],
+            base_path: dir.to_string_lossy().to_string(),
+            format: "markdown".to_string(),
+            ignore_patterns: vec!["*.log".to_string()],
+            ..Default::default()
+        };
+        let root = dir.file_name().unwrap().to_string_lossy().to_string();
+
+        let full = render_tree(
+            &request,
+            &TreeOptions {
+                max_depth: None,
+                show_sizes: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            full,
+            format!(
+                "{}/\n\
+                 ├── src/\n\
+                 │   ├── nested/\n\
+                 │   │   └── deep.rs (0 B)\n\
+                 │   └── main.rs (13 B) *\n\
+                 └── README.md (2.0 KB)\n\
+                 (* = included below)\n",
+                root
+            )
+        );
+
+        let shallow = render_tree(
+            &request,
+            &TreeOptions {
+                max_depth: Some(1),
+                show_sizes: false,
+            },
+        )
+        .unwrap();
+        assert!(shallow.contains("├── src/ ... 2 entries, 1 included\n└── README.md\n"));
+
+        // Files read only through a selection are marked too
+        let request = CopyRequest {
+            files: Vec::new(),
+            selections: vec![Selection {
+                file: dir
+                    .join("src")
+                    .join("nested")
+                    .join("deep.rs")
+                    .to_string_lossy()
+                    .to_string(),
+                target: SelectionTarget::Lines { start: 1, end: 1 },
+            }],
+            ..request
+        };
+        let marked = render_tree(&request, &TreeOptions::default()).unwrap();
+        assert!(marked.contains("deep.rs *\n") && !marked.contains("main.rs *"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+use rayon::prelude::*;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::git::FileHistory;
+use crate::jobs::JobContext;
+
+// Only this many leading bytes are inspected to decide whether a file is binary
+const SNIFF_LEN: usize = 8000;
+
+/// What was found when reading a selected file
+#[derive(Debug, Clone)]
+pub enum FileBody {
+    Text(String),
+    Binary,
+    TooLarge,
+    Missing,
+    Error(String),
+}
+
+/// A selected file, read once and ready to be formatted
+#[derive(Debug, Clone)]
+pub struct LoadedFile {
+    pub path: PathBuf,
+    pub relative_path: String,
+    pub size: u64,
+    pub body: FileBody,
+    /// Last commit and blame, when the request asked for them
+    pub history: Option<FileHistory>,
+    /// 1-based inclusive lines when only part of the file is copied
+    pub lines: Option<(usize, usize)>,
+}
+
+/// Read `files` across the thread pool, keeping the order they were given in.
+///
+/// Directories are skipped. Every file is opened once: a bounded prefix is
+/// sniffed for binary content and the rest is only read if the file is text
+/// and under `max_size` bytes.
+pub fn load_files(
+    files: &[String],
+    base_path: &Path,
+    max_size: u64,
+    job: &JobContext,
+) -> Result<Vec<LoadedFile>, AppError> {
+    let loaded: Option<Vec<Option<LoadedFile>>> = files
+        .par_iter()
+        .map(|file_path| {
+            if job.is_cancelled() {
+                return None;
+            }
+
+            let loaded = load_file(Path::new(file_path), base_path, max_size);
+            job.record(Path::new(file_path), loaded.as_ref().map_or(0, |f| f.size));
+            Some(loaded)
+        })
+        .collect();
+
+    match loaded {
+        Some(files) if !job.is_cancelled() => Ok(files.into_iter().flatten().collect()),
+        _ => Err(AppError::cancelled("Read cancelled")),
+    }
+}
+
+/// Read a single file, or `None` if the path is a directory
+pub fn load_file(path: &Path, base_path: &Path, max_size: u64) -> Option<LoadedFile> {
+    let relative_path = get_relative_path(path, base_path);
+    let loaded = |size, body| LoadedFile {
+        path: path.to_path_buf(),
+        relative_path: relative_path.clone(),
+        size,
+        body,
+        history: None,
+        lines: None,
+    };
+
+    let metadata = match path.metadata() {
+        Ok(metadata) => metadata,
+        Err(_) if !path.exists() => return Some(loaded(0, FileBody::Missing)),
+        Err(e) => return Some(loaded(0, FileBody::Error(e.to_string()))),
+    };
+
+    if !metadata.is_file() {
+        return None;
+    }
+
+    let size = metadata.len();
+    Some(loaded(size, read_body(path, size, max_size)))
+}
+
+fn read_body(path: &Path, size: u64, max_size: u64) -> FileBody {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => return FileBody::Error(e.to_string()),
+    };
+
+    let mut buffer = Vec::with_capacity(SNIFF_LEN.min(size as usize));
+    if let Err(e) = (&mut file).take(SNIFF_LEN as u64).read_to_end(&mut buffer) {
+        return FileBody::Error(e.to_string());
+    }
+
+    if buffer.contains(&0) {
+        return FileBody::Binary;
+    }
+
+    if size > max_size {
+        return FileBody::TooLarge;
+    }
+
+    buffer.reserve((size as usize).saturating_sub(buffer.len()));
+    if let Err(e) = file.read_to_end(&mut buffer) {
+        return FileBody::Error(e.to_string());
+    }
+
+    match String::from_utf8(buffer) {
+        Ok(content) => FileBody::Text(content),
+        Err(_) => FileBody::Error("stream did not contain valid UTF-8".to_string()),
+    }
+}
+
+pub fn get_relative_path(path: &Path, base_path: &Path) -> String {
+    match path.strip_prefix(base_path) {
+        Ok(p) => p.to_string_lossy().to_string(),
+        Err(_) => path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_load_files_keeps_order() {
+        let dir = std::env::temp_dir().join(format!("filesurfer_pipeline_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut files = Vec::new();
+        for i in 0..32 {
+            let path = dir.join(format!("{:02}.txt", i));
+            fs::write(&path, format!("file {}", i)).unwrap();
+            files.push(path.to_string_lossy().to_string());
+        }
+        fs::write(dir.join("blob.bin"), [1u8, 0, 2]).unwrap();
+        fs::write(dir.join("big.txt"), "x".repeat(64)).unwrap();
+        files.push(dir.join("blob.bin").to_string_lossy().to_string());
+        files.push(dir.join("big.txt").to_string_lossy().to_string());
+        files.push(dir.join("gone.txt").to_string_lossy().to_string());
+
+        let loaded = load_files(&files, &dir, 32, &JobContext::detached()).unwrap();
+        assert_eq!(loaded.len(), 35);
+        assert!(matches!(&loaded[7].body, FileBody::Text(t) if t == "file 7"));
+        assert_eq!(loaded[7].relative_path, "07.txt");
+        assert!(matches!(loaded[32].body, FileBody::Binary));
+        assert!(matches!(loaded[33].body, FileBody::TooLarge));
+        assert!(matches!(loaded[34].body, FileBody::Missing));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::code_analysis::extract_functions_from;
+use crate::read_pipeline::{FileBody, LoadedFile};
+
+/// Shows files as their API surface, with function bodies elided
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SkeletonOptions {
+    /// Files kept in full, given like `CopyRequest::files`
+    #[serde(default)]
+    pub focus_files: Vec<String>,
+}
+
+/// Replace every file outside the focus with its skeleton. Slices picked
+/// out of a file are kept whole too.
+pub fn skeleton_loaded(files: &mut [LoadedFile], options: &SkeletonOptions) {
+    for file in files {
+        if file.lines.is_some()
+            || options
+                .focus_files
+                .iter()
+                .any(|focus| Path::new(focus) == file.path)
+        {
+            continue;
+        }
+        if let FileBody::Text(content) = &file.body {
+            file.body = FileBody::Text(skeletonize(&file.path.to_string_lossy(), content));
+        }
+    }
+}
+
+/// `content` with function bodies replaced by `{ ... }`, or `...` in Python,
+/// using the ranges from `extract_functions`. Imports, types and the doc
+/// comments above each function are left alone; Python docstrings are kept.
+pub fn skeletonize(file_path: &str, content: &str) -> String {
+    let Ok(mut functions) = extract_functions_from(file_path, content) else {
+        return content.to_string();
+    };
+    functions.sort_by_key(|function| function.line_start);
+    let python = file_path.ends_with(".py");
+    let lines: Vec<&str> = content.lines().collect();
+
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+    let mut next = 0;
+    for function in functions {
+        let start = function.line_start - 1;
+        // Nested functions went with the body they are in
+        if start < next || start >= lines.len() {
+            continue;
+        }
+        let end = function.line_end.min(lines.len());
+        output.extend(lines[next..start].iter().map(|line| line.to_string()));
+
+        let first = lines[start];
+        let indent = &first[..first.len() - first.trim_start().len()];
+        if python {
+            output.push(format!("{}{}", indent, function.signature));
+            output.extend(docstring(&lines[start..end]).iter().map(|l| l.to_string()));
+            output.push(format!("{}    ...", indent));
+        } else {
+            output.push(format!("{}{} {{ ... }}", indent, function.signature));
+        }
+        next = end;
+    }
+    output.extend(lines[next..].iter().map(|line| line.to_string()));
+
+    let mut skeleton = output.join("\n");
+    if content.ends_with('\n') {
+        skeleton.push('\n');
+    }
+    skeleton
+}
+
+/// The docstring opening a Python function, if it has one
+fn docstring<'a, 'b>(function: &'b [&'a str]) -> &'b [&'a str] {
+    let Some(header_end) = function
+        .iter()
+        .position(|line| line.trim_end().ends_with(':'))
+    else {
+        return &[];
+    };
+    let body = &function[header_end + 1..];
+    let Some(first) = body.iter().position(|line| !line.trim().is_empty()) else {
+        return &[];
+    };
+
+    let opening = body[first].trim_start().trim_start_matches(['r', 'R']);
+    let Some(quote) = ["\"\"\"", "'''"]
+        .into_iter()
+        .find(|quote| opening.starts_with(quote))
+    else {
+        return &[];
+    };
+    if opening[quote.len()..].contains(quote) {
+        return &body[first..=first];
+    }
+    match body[first + 1..]
+        .iter()
+        .position(|line| line.contains(quote))
+    {
+        Some(last) => &body[first..=first + 1 + last],
+        None => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skeleton_keeps_signatures_and_docs() {
+        let rust = r#"use std::fmt;
+
+/// A counter
+pub struct Counter(u32);
+
+impl Counter {
+    /// Adds one
+    pub fn bump(&mut self) -> u32 {
+        fn helper() {}
+        self.0 += 1;
+        self.0
+    }
+}
+"#;
+        assert_eq!(
+            skeletonize("counter.rs", rust),
+            r#"use std::fmt;
+
+/// A counter
+pub struct Counter(u32);
+
+impl Counter {
+    /// Adds one
+    pub fn bump(&mut self) -> u32 { ... }
+}
+"#
+        );
+
+        let python = "import os\n\ndef load(path,\n         mode='r'):\n    \"\"\"Read a file.\n\n    Returns text.\"\"\"\n    return open(path, mode).read()\n";
+        assert_eq!(
+            skeletonize("io.py", python),
+            "import os\n\ndef load(path, mode='r'):\n    \"\"\"Read a file.\n\n    Returns text.\"\"\"\n    ...\n"
+        );
+
+        assert_eq!(skeletonize("notes.txt", "fn main() {}"), "fn main() {}");
+    }
+}
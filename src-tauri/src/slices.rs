@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::code_analysis::{extract_functions_from, Symbol};
+use crate::read_pipeline::{FileBody, LoadedFile};
+use crate::syntax;
+
+/// Part of a file to copy instead of all of it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Selection {
+    pub file: String,
+    #[serde(flatten)]
+    pub target: SelectionTarget,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SelectionTarget {
+    /// Every outline symbol or function with this name. `Type.method` or
+    /// `Type::method` picks a member of one type.
+    Symbol { name: String },
+    /// 1-based and inclusive
+    Lines { start: usize, end: usize },
+}
+
+/// Replace each file that has selections with one entry per selected range.
+/// Overlapping and adjacent ranges are merged; a target that cannot be found
+/// becomes an error entry so it does not go missing silently.
+pub fn slice_loaded(files: Vec<LoadedFile>, selections: &[Selection]) -> Vec<LoadedFile> {
+    if selections.is_empty() {
+        return files;
+    }
+
+    let mut sliced = Vec::with_capacity(files.len());
+    for file in files {
+        let targets: Vec<&SelectionTarget> = selections
+            .iter()
+            .filter(|selection| Path::new(&selection.file) == file.path)
+            .map(|selection| &selection.target)
+            .collect();
+        if targets.is_empty() {
+            sliced.push(file);
+            continue;
+        }
+        // Unreadable files show their error once, as they would whole
+        let FileBody::Text(content) = &file.body else {
+            sliced.push(file);
+            continue;
+        };
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut ranges = Vec::new();
+        for target in targets {
+            match resolve(target, &file, content, lines.len()) {
+                Ok(found) => ranges.extend(found),
+                Err(message) => sliced.push(LoadedFile {
+                    body: FileBody::Error(message),
+                    ..file.clone()
+                }),
+            }
+        }
+
+        for (start, end) in merge(ranges) {
+            sliced.push(slice(&file, &lines, start, end));
+        }
+    }
+    sliced
+}
+
+fn resolve(
+    target: &SelectionTarget,
+    file: &LoadedFile,
+    content: &str,
+    line_count: usize,
+) -> Result<Vec<(usize, usize)>, String> {
+    match target {
+        SelectionTarget::Lines { start, end } => {
+            if *start == 0 || start > end || *start > line_count {
+                return Err(format!(
+                    "Lines {}-{} are outside the file's {} lines",
+                    start, end, line_count
+                ));
+            }
+            Ok(vec![(*start, (*end).min(line_count))])
+        }
+        SelectionTarget::Symbol { name } => {
+            let path = file.path.to_string_lossy();
+            let extension = file.path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+            let mut ranges = Vec::new();
+            if let Some(symbols) = syntax::outline(content, extension) {
+                find_symbols(&symbols, name, &mut ranges);
+            }
+            // Nested functions and languages without a grammar
+            if ranges.is_empty() {
+                ranges.extend(
+                    extract_functions_from(&path, content)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|function| &function.name == name)
+                        .map(|function| (function.line_start, function.line_end)),
+                );
+            }
+
+            if ranges.is_empty() {
+                Err(format!("No symbol named `{}`", name))
+            } else {
+                Ok(ranges)
+            }
+        }
+    }
+}
+
+fn find_symbols(symbols: &[Symbol], name: &str, ranges: &mut Vec<(usize, usize)>) {
+    for symbol in symbols {
+        let qualified = symbol.parent.as_ref().is_some_and(|parent| {
+            name.strip_prefix(parent.as_str())
+                .and_then(|rest| rest.strip_prefix('.').or_else(|| rest.strip_prefix("::")))
+                == Some(symbol.name.as_str())
+        });
+        if symbol.name == name || qualified {
+            ranges.push((symbol.line_start, symbol.line_end));
+        } else {
+            find_symbols(&symbol.children, name, ranges);
+        }
+    }
+}
+
+fn merge(mut ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Lines `start..=end` of `file`, keeping the blame for just those lines
+fn slice(file: &LoadedFile, lines: &[&str], start: usize, end: usize) -> LoadedFile {
+    let content = lines[start - 1..end].join("\n");
+    let history = file.history.clone().map(|mut history| {
+        history
+            .blame
+            .retain(|range| range.end_line >= start && range.start_line <= end);
+        for range in &mut history.blame {
+            range.start_line = range.start_line.max(start);
+            range.end_line = range.end_line.min(end);
+        }
+        history
+    });
+
+    LoadedFile {
+        path: file.path.clone(),
+        relative_path: file.relative_path.clone(),
+        size: content.len() as u64,
+        body: FileBody::Text(content),
+        history,
+        lines: Some((start, end)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_symbols_and_ranges_become_slices() {
+        let content = "\
+pub struct Counter(u32);
+
+impl Counter {
+    pub fn new() -> Self {
+        Counter(0)
+    }
+
+    pub fn bump(&mut self) {
+        self.0 += 1;
+    }
+}
+
+fn main() {}
+";
+        let file = LoadedFile {
+            path: PathBuf::from("/project/src/counter.rs"),
+            relative_path: "src/counter.rs".to_string(),
+            size: content.len() as u64,
+            body: FileBody::Text(content.to_string()),
+            history: None,
+            lines: None,
+        };
+        let select = |target| Selection {
+            file: "/project/src/counter.rs".to_string(),
+            target,
+        };
+        let symbol = |name: &str| {
+            select(SelectionTarget::Symbol {
+                name: name.to_string(),
+            })
+        };
+
+        let sliced = slice_loaded(
+            vec![file.clone()],
+            &[
+                symbol("Counter::bump"),
+                select(SelectionTarget::Lines { start: 13, end: 99 }),
+                symbol("missing"),
+                symbol("Counter"),
+            ],
+        );
+        let summary: Vec<(Option<(usize, usize)>, String)> = sliced
+            .iter()
+            .map(|file| {
+                let body = match &file.body {
+                    FileBody::Text(content) => content.lines().next().unwrap_or("").to_string(),
+                    FileBody::Error(e) => e.clone(),
+                    _ => String::new(),
+                };
+                (file.lines, body)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (None, "No symbol named `missing`".to_string()),
+                (Some((1, 1)), "pub struct Counter(u32);".to_string()),
+                (Some((3, 11)), "impl Counter {".to_string()),
+                (Some((13, 13)), "fn main() {}".to_string()),
+            ]
+        );
+
+        // Files without selections pass through whole
+        let untouched = slice_loaded(vec![file], &[]);
+        assert_eq!(untouched[0].lines, None);
+    }
+}
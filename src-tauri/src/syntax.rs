@@ -0,0 +1,599 @@
+use tree_sitter::{Language, Node, Parser, Tree};
+
+use crate::code_analysis::{Dependency, Function, Symbol, SymbolKind, Visibility};
+
+/// Languages with a tree-sitter grammar compiled in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    Rust,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Python,
+}
+
+impl Lang {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "rs" => Some(Lang::Rust),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Lang::JavaScript),
+            "ts" | "mts" | "cts" => Some(Lang::TypeScript),
+            "tsx" => Some(Lang::Tsx),
+            "py" => Some(Lang::Python),
+            _ => None,
+        }
+    }
+
+    fn grammar(self) -> Language {
+        match self {
+            Lang::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Lang::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Lang::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Lang::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Lang::Python => tree_sitter_python::LANGUAGE.into(),
+        }
+    }
+}
+
+pub fn parse(content: &str, lang: Lang) -> Option<Tree> {
+    let mut parser = Parser::new();
+    parser.set_language(&lang.grammar()).ok()?;
+    parser.parse(content, None)
+}
+
+/// Visit every node in document order
+pub fn walk<'t>(tree: &'t Tree, mut visit: impl FnMut(Node<'t>)) {
+    let mut cursor = tree.walk();
+    loop {
+        visit(cursor.node());
+        if cursor.goto_first_child() {
+            continue;
+        }
+        loop {
+            if cursor.goto_next_sibling() {
+                break;
+            }
+            if !cursor.goto_parent() {
+                return;
+            }
+        }
+    }
+}
+
+pub fn text<'a>(node: Node, content: &'a str) -> &'a str {
+    &content[node.byte_range()]
+}
+
+/// Source from `start` up to where `body` begins, on one line
+pub fn signature(start: Node, body: Node, content: &str) -> String {
+    content[start.start_byte()..body.start_byte()]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Functions, methods and named function expressions, nested ones included.
+/// `None` when the extension has no grammar.
+pub fn functions(file: &str, content: &str, extension: &str) -> Option<Vec<Function>> {
+    let lang = Lang::from_extension(extension)?;
+    let tree = parse(content, lang)?;
+    let lines: Vec<&str> = content.lines().collect();
+    let mut functions = Vec::new();
+
+    walk(&tree, |node| {
+        let Some((outer, name, body)) = function_parts(node, lang) else {
+            return;
+        };
+        let line_start = outer.start_position().row;
+        let line_end = outer.end_position().row.min(lines.len().saturating_sub(1));
+
+        functions.push(Function {
+            file: file.to_string(),
+            name: text(name, content).to_string(),
+            signature: signature(outer, body, content),
+            line_start: line_start + 1,
+            line_end: line_end + 1,
+            content: lines
+                .get(line_start..=line_end)
+                .unwrap_or_default()
+                .join("\n"),
+        });
+    });
+
+    Some(functions)
+}
+
+/// The node spanning a function with its name and body, if `node` starts one.
+/// Arrow functions and function expressions take their name and span from
+/// the declaration they are assigned in.
+fn function_parts(node: Node, lang: Lang) -> Option<(Node, Node, Node)> {
+    let named = match lang {
+        Lang::Rust => node.kind() == "function_item",
+        Lang::Python => node.kind() == "function_definition",
+        Lang::JavaScript | Lang::TypeScript | Lang::Tsx => matches!(
+            node.kind(),
+            "function_declaration" | "generator_function_declaration" | "method_definition"
+        ),
+    };
+    if named {
+        return Some((
+            node,
+            node.child_by_field_name("name")?,
+            node.child_by_field_name("body")?,
+        ));
+    }
+
+    if lang != Lang::Rust && lang != Lang::Python && node.kind() == "variable_declarator" {
+        let value = node.child_by_field_name("value")?;
+        if !matches!(
+            value.kind(),
+            "arrow_function" | "function_expression" | "function"
+        ) {
+            return None;
+        }
+        let outer = node
+            .parent()
+            .filter(|parent| parent.kind().ends_with("declaration"))
+            .unwrap_or(node);
+        return Some((
+            outer,
+            node.child_by_field_name("name")?,
+            value.child_by_field_name("body")?,
+        ));
+    }
+
+    None
+}
+
+/// Top-level symbols with their members nested under them. `None` when the
+/// extension has no grammar.
+pub fn outline(content: &str, extension: &str) -> Option<Vec<Symbol>> {
+    let lang = Lang::from_extension(extension)?;
+    let tree = parse(content, lang)?;
+    let mut symbols = Vec::new();
+    collect_symbols(tree.root_node(), lang, content, None, &mut symbols);
+    Some(symbols)
+}
+
+/// The symbol whose members are being collected
+struct Scope<'a> {
+    kind: SymbolKind,
+    name: &'a str,
+    visibility: Option<Visibility>,
+}
+
+fn collect_symbols(
+    container: Node,
+    lang: Lang,
+    content: &str,
+    scope: Option<&Scope>,
+    symbols: &mut Vec<Symbol>,
+) {
+    let mut cursor = container.walk();
+    for node in container.named_children(&mut cursor) {
+        let symbol = match node.kind() {
+            "export_statement" => node
+                .child_by_field_name("declaration")
+                .and_then(|declaration| symbol(declaration, node, lang, content, scope, true)),
+            "decorated_definition" => node
+                .child_by_field_name("definition")
+                .and_then(|definition| symbol(definition, node, lang, content, scope, false)),
+            _ => symbol(node, node, lang, content, scope, false),
+        };
+        symbols.extend(symbol);
+    }
+}
+
+/// `outer` is `node` or the export or decorators wrapping it, and sets the
+/// line range together with any doc comments and attributes just above it.
+fn symbol(
+    node: Node,
+    outer: Node,
+    lang: Lang,
+    content: &str,
+    scope: Option<&Scope>,
+    exported: bool,
+) -> Option<Symbol> {
+    use SymbolKind::*;
+
+    let callable = match scope {
+        Some(scope) if scope.kind != Module => Method,
+        _ => Function,
+    };
+    let mut body = node.child_by_field_name("body");
+    let mut named = node;
+    let (kind, field) = match (lang, node.kind()) {
+        (Lang::Rust, "function_item" | "function_signature_item") => (callable, "name"),
+        (Lang::Rust, "struct_item" | "union_item") => (Struct, "name"),
+        (Lang::Rust, "enum_item") => (Enum, "name"),
+        (Lang::Rust, "trait_item") => (Trait, "name"),
+        (Lang::Rust, "impl_item") => (Impl, "type"),
+        (Lang::Rust, "mod_item") => (Module, "name"),
+        (Lang::Rust, "const_item" | "static_item") => (Constant, "name"),
+        (Lang::Rust, "type_item") => (TypeAlias, "name"),
+        (Lang::Python, "class_definition") => (Class, "name"),
+        (Lang::Python, "function_definition") => (callable, "name"),
+        (Lang::Rust | Lang::Python, _) => return None,
+        (_, "class_declaration" | "abstract_class_declaration") => (Class, "name"),
+        (_, "interface_declaration") => (Interface, "name"),
+        (_, "enum_declaration") => (Enum, "name"),
+        (_, "type_alias_declaration") => (TypeAlias, "name"),
+        (_, "function_declaration" | "generator_function_declaration") => (Function, "name"),
+        (_, "method_definition" | "method_signature" | "abstract_method_signature") => {
+            (Method, "name")
+        }
+        (_, "lexical_declaration" | "variable_declaration") if scope.is_none() => {
+            // `const load = () => {}`
+            named = node.named_child(0)?;
+            body = Some(function_parts(named, lang)?.2);
+            (Function, "name")
+        }
+        _ => return None,
+    };
+    let name = text(named.child_by_field_name(field)?, content);
+
+    let signature = match body.or_else(|| node.child_by_field_name("value")) {
+        Some(end) => signature(node, end, content)
+            .trim_end_matches([' ', '='])
+            .to_string(),
+        None => text(node, content)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim_end_matches(';')
+            .to_string(),
+    };
+    let visibility = visibility(node, kind, name, lang, content, scope, exported);
+
+    let mut children = Vec::new();
+    if let Some(body) = body.filter(|_| matches!(kind, Impl | Trait | Class | Interface | Module)) {
+        let inner = Scope {
+            kind,
+            name,
+            visibility,
+        };
+        collect_symbols(body, lang, content, Some(&inner), &mut children);
+    }
+
+    Some(Symbol {
+        kind,
+        name: name.to_string(),
+        parent: scope.map(|scope| scope.name.to_string()),
+        visibility,
+        signature,
+        line_start: leading_start(outer, content).start_position().row + 1,
+        line_end: outer.end_position().row + 1,
+        children,
+    })
+}
+
+fn visibility(
+    node: Node,
+    kind: SymbolKind,
+    name: &str,
+    lang: Lang,
+    content: &str,
+    scope: Option<&Scope>,
+    exported: bool,
+) -> Option<Visibility> {
+    let modifier = |kind: &str| {
+        let mut cursor = node.walk();
+        let found = node
+            .children(&mut cursor)
+            .find(|child| child.kind() == kind)
+            .map(|child| text(child, content));
+        found
+    };
+
+    Some(match lang {
+        Lang::Rust if kind == SymbolKind::Impl => return None,
+        Lang::Rust => match modifier("visibility_modifier") {
+            Some("pub") => Visibility::Public,
+            Some(_) => Visibility::Restricted,
+            // Trait members are as visible as the trait
+            None => match scope {
+                Some(scope) if scope.kind == SymbolKind::Trait => scope.visibility?,
+                _ => Visibility::Private,
+            },
+        },
+        Lang::Python => {
+            let dunder = name.starts_with("__") && name.ends_with("__");
+            if name.starts_with('_') && !dunder {
+                Visibility::Private
+            } else {
+                Visibility::Public
+            }
+        }
+        _ if scope.is_some() => match modifier("accessibility_modifier") {
+            Some("private") => Visibility::Private,
+            Some("protected") => Visibility::Protected,
+            _ if name.starts_with('#') => Visibility::Private,
+            _ => Visibility::Public,
+        },
+        _ if exported => Visibility::Public,
+        _ => Visibility::Private,
+    })
+}
+
+/// Widen `node` over the doc comments and attributes directly above it
+fn leading_start<'t>(node: Node<'t>, content: &str) -> Node<'t> {
+    let mut start = node;
+    while let Some(previous) = start.prev_named_sibling() {
+        let adjacent = previous.end_position().row + 1 >= start.start_position().row;
+        let leading = previous.kind() == "attribute_item"
+            || (previous.kind().ends_with("comment")
+                && ["///", "/**"]
+                    .iter()
+                    .any(|doc| text(previous, content).starts_with(doc)));
+        if !adjacent || !leading {
+            break;
+        }
+        start = previous;
+    }
+    start
+}
+
+/// Imports in the order they appear. `None` when the extension has no grammar.
+pub fn dependencies(file: &str, content: &str, extension: &str) -> Option<Vec<Dependency>> {
+    let lang = Lang::from_extension(extension)?;
+    let tree = parse(content, lang)?;
+    let mut dependencies = Vec::new();
+
+    let mut push = |node: Node, dependency: &str, import_type: &str| {
+        dependencies.push(Dependency {
+            file: file.to_string(),
+            dependency: dependency.to_string(),
+            import_type: import_type.to_string(),
+            line_number: node.start_position().row + 1,
+        });
+    };
+
+    walk(&tree, |node| match (lang, node.kind()) {
+        (Lang::Rust, "use_declaration") => {
+            if let Some(argument) = node.child_by_field_name("argument") {
+                push(node, text(argument, content), "use");
+            }
+        }
+        // `mod name;` pulls in another file; inline modules do not
+        (Lang::Rust, "mod_item") if node.child_by_field_name("body").is_none() => {
+            if let Some(name) = node.child_by_field_name("name") {
+                push(node, text(name, content), "mod");
+            }
+        }
+        (Lang::Python, "import_statement") => {
+            let mut cursor = node.walk();
+            for name in node.children_by_field_name("name", &mut cursor) {
+                // `import a.b as c` depends on a.b
+                let module = name.child_by_field_name("name").unwrap_or(name);
+                push(node, text(module, content), "import");
+            }
+        }
+        (Lang::Python, "import_from_statement") => {
+            if let Some(module) = node.child_by_field_name("module_name") {
+                push(node, text(module, content), "from");
+            }
+        }
+        (Lang::Rust | Lang::Python, _) => {}
+        (_, "import_statement") => {
+            if let Some(source) = node.child_by_field_name("source") {
+                push(node, unquote(text(source, content)), "import");
+            }
+        }
+        (_, "export_statement") => {
+            if let Some(source) = node.child_by_field_name("source") {
+                push(node, unquote(text(source, content)), "export");
+            }
+        }
+        (_, "call_expression") => {
+            let Some(function) = node.child_by_field_name("function") else {
+                return;
+            };
+            let import_type = match (function.kind(), text(function, content)) {
+                ("identifier", "require") => "require",
+                ("import", _) => "import",
+                _ => return,
+            };
+            let source = node
+                .child_by_field_name("arguments")
+                .and_then(|arguments| arguments.named_child(0))
+                .filter(|argument| argument.kind() == "string");
+            if let Some(source) = source {
+                push(node, unquote(text(source, content)), import_type);
+            }
+        }
+        _ => {}
+    });
+
+    Some(dependencies)
+}
+
+fn unquote(literal: &str) -> &str {
+    literal.trim_matches(|c| c == '"' || c == '\'' || c == '`')
+}
+
+/// Remove comments, dropping lines left empty by it. Strings that look like
+/// comments are kept. `None` when the extension has no grammar.
+pub fn strip_comments(content: &str, extension: &str) -> Option<String> {
+    let tree = parse(content, Lang::from_extension(extension)?)?;
+
+    let mut comments = Vec::new();
+    walk(&tree, |node| {
+        if node.kind().ends_with("comment") {
+            comments.push(node.byte_range());
+        }
+    });
+
+    let mut stripped = String::with_capacity(content.len());
+    let mut copied = 0;
+    for range in comments {
+        // Doc comments contain nested comment nodes
+        if range.start < copied {
+            continue;
+        }
+        stripped.push_str(&content[copied..range.start]);
+        // Keep the line breaks so lines still match the original
+        stripped.extend(content[range.clone()].chars().filter(|&c| c == '\n'));
+        copied = range.end;
+    }
+    stripped.push_str(&content[copied..]);
+
+    Some(
+        stripped
+            .lines()
+            .zip(content.lines())
+            .filter(|(now, before)| !now.trim().is_empty() || before.trim().is_empty())
+            .map(|(now, before)| if now == before { now } else { now.trim_end() })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(functions: &[Function]) -> Vec<(&str, usize, usize)> {
+        functions
+            .iter()
+            .map(|f| (f.name.as_str(), f.line_start, f.line_end))
+            .collect()
+    }
+
+    #[test]
+    fn test_parsing_handles_what_regexes_missed() {
+        let rust = r#"pub fn outer(
+    name: &str,
+) -> String {
+    let brace = "}";
+    let add = |a: u32| a + 1;
+    fn inner() {}
+    format!("{}{}", name, brace)
+}
+"#;
+        let found = functions("lib.rs", rust, "rs").unwrap();
+        assert_eq!(names(&found), vec![("outer", 1, 8), ("inner", 6, 6)]);
+        assert_eq!(found[0].signature, "pub fn outer( name: &str, ) -> String");
+
+        let python = "class Greeter:\n    async def greet(self):\n        return '#'\n\ndef main():\n    pass\n";
+        let found = functions("app.py", python, "py").unwrap();
+        assert_eq!(names(&found), vec![("greet", 2, 3), ("main", 5, 6)]);
+        assert_eq!(found[0].signature, "async def greet(self):");
+
+        let js = "import React from 'react';\nexport { x } from \"./x\";\nconst load = async () => {\n  return require('./data');\n};\n";
+        assert_eq!(
+            names(&functions("app.js", js, "js").unwrap()),
+            vec![("load", 3, 5)]
+        );
+        let deps: Vec<(String, String)> = dependencies("app.js", js, "js")
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.dependency, d.import_type))
+            .collect();
+        assert_eq!(
+            deps,
+            vec![
+                ("react".to_string(), "import".to_string()),
+                ("./x".to_string(), "export".to_string()),
+                ("./data".to_string(), "require".to_string()),
+            ]
+        );
+
+        let python_deps: Vec<String> = dependencies(
+            "app.py",
+            "import os, json as j\nfrom .utils import x\n",
+            "py",
+        )
+        .unwrap()
+        .into_iter()
+        .map(|d| d.dependency)
+        .collect();
+        assert_eq!(python_deps, vec!["os", "json", ".utils"]);
+
+        let commented = "/// Docs\nfn main() {\n    let url = \"http://example.com\"; // trailing\n\n    /* block */\n}\n";
+        assert_eq!(
+            strip_comments(commented, "rs").unwrap(),
+            "fn main() {\n    let url = \"http://example.com\";\n\n}"
+        );
+
+        assert!(functions("notes.txt", "", "txt").is_none());
+    }
+
+    fn flatten(symbols: &[Symbol]) -> Vec<String> {
+        let mut flat = Vec::new();
+        for symbol in symbols {
+            flat.push(format!(
+                "{:?} {}.{} {:?} {}-{}",
+                symbol.kind,
+                symbol.parent.as_deref().unwrap_or(""),
+                symbol.name,
+                symbol.visibility,
+                symbol.line_start,
+                symbol.line_end
+            ));
+            flat.extend(flatten(&symbol.children));
+        }
+        flat
+    }
+
+    #[test]
+    fn test_outline_nests_members_under_their_types() {
+        let rust = r#"/// A point
+#[derive(Debug)]
+pub struct Point(u32);
+
+pub(crate) trait Shape {
+    fn area(&self) -> u32;
+}
+
+impl Shape for Point {
+    fn area(&self) -> u32 {
+        self.0
+    }
+}
+
+const MAX: u32 = 10;
+"#;
+        let symbols = outline(rust, "rs").unwrap();
+        assert_eq!(
+            flatten(&symbols),
+            vec![
+                "Struct .Point Some(Public) 1-3",
+                "Trait .Shape Some(Restricted) 5-7",
+                "Method Shape.area Some(Restricted) 6-6",
+                "Impl .Point None 9-13",
+                "Method Point.area Some(Private) 10-12",
+                "Constant .MAX Some(Private) 15-15",
+            ]
+        );
+        assert_eq!(symbols[1].children[0].signature, "fn area(&self) -> u32");
+        assert_eq!(symbols[2].signature, "impl Shape for Point");
+        assert_eq!(symbols[3].signature, "const MAX: u32");
+
+        let ts = "export interface Named {\n  name(): string;\n}\n\nclass Greeter {\n  private secret() {}\n  greet() {}\n}\n\nexport const load = async () => {};\n";
+        let symbols = outline(ts, "ts").unwrap();
+        assert_eq!(
+            flatten(&symbols),
+            vec![
+                "Interface .Named Some(Public) 1-3",
+                "Method Named.name Some(Public) 2-2",
+                "Class .Greeter Some(Private) 5-8",
+                "Method Greeter.secret Some(Private) 6-6",
+                "Method Greeter.greet Some(Public) 7-7",
+                "Function .load Some(Public) 10-10",
+            ]
+        );
+        assert_eq!(symbols[2].signature, "const load = async () =>");
+
+        let python = "class Cache(Base):\n    @property\n    def _size(self):\n        return 0\n\n    def __len__(self):\n        return 0\n";
+        let symbols = outline(python, "py").unwrap();
+        assert_eq!(
+            flatten(&symbols),
+            vec![
+                "Class .Cache Some(Public) 1-7",
+                "Method Cache._size Some(Private) 2-4",
+                "Method Cache.__len__ Some(Public) 6-7",
+            ]
+        );
+        assert_eq!(symbols[0].signature, "class Cache(Base):");
+    }
+}
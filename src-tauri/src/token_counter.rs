@@ -1,73 +1,142 @@
 use serde::{Deserialize, Serialize};
 
+use rayon::prelude::*;
+
+use crate::config::ModelConfig;
+use crate::error::AppError;
+use crate::tokenizer::{bpe_tokenizer, RatioTokenizer, ScaledTokenizer, Tokenizer};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TokenEstimate {
+    /// Count for the first model in the registry
     pub total_tokens: u32,
     pub char_count: usize,
     pub word_count: usize,
     pub line_count: usize,
-    pub gpt4_estimate: u32,
-    pub claude_estimate: u32,
-    pub gemini_estimate: u32,
+    pub models: Vec<ModelEstimate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelEstimate {
+    pub model: String,
+    pub name: String,
+    pub tokenizer: String,
+    pub tokens: u32,
+    pub exact: bool,
+    pub context_window: u32,
+    pub context_used_percent: f64,
+    pub cost: f64,
 }
 
-/// Estimate tokens for various AI models
-/// GPT models: ~4 chars = 1 token, ~0.75 words = 1 token
-/// Claude models: Similar to GPT, slightly different on special chars
-pub fn estimate_tokens(text: &str) -> TokenEstimate {
+/// Count tokens for every model in the registry
+pub fn estimate_tokens(text: &str, models: &[ModelConfig]) -> Result<TokenEstimate, AppError> {
     let char_count = text.len();
     let word_count = text.split_whitespace().count();
     let line_count = text.lines().count();
 
-    // GPT-4 estimation: Average of char-based and word-based
-    // More accurate formula based on actual token behavior
-    let char_tokens = (char_count as f32 / 4.0) as u32;
-    let word_tokens = (word_count as f32 * 1.3) as u32;
-    let gpt4_estimate = ((char_tokens + word_tokens) / 2).max(1);
-
-    // Claude estimation: Slightly more tokens for same content
-    let claude_estimate = (gpt4_estimate as f32 * 1.05) as u32;
+    let models = models
+        .par_iter()
+        .map(|model| {
+            let tokenizer = model_tokenizer(model)?;
+            let tokens = tokenizer.count(text);
 
-    // Gemini estimation: ~10% more efficient than GPT-4 on average for code
-    let gemini_estimate = (gpt4_estimate as f32 * 0.9) as u32;
+            Ok(ModelEstimate {
+                model: model.id.clone(),
+                name: model.name.clone(),
+                tokenizer: tokenizer.name().to_string(),
+                tokens,
+                exact: tokenizer.is_exact(),
+                context_window: model.context_window,
+                context_used_percent: if model.context_window > 0 {
+                    tokens as f64 / model.context_window as f64 * 100.0
+                } else {
+                    0.0
+                },
+                cost: tokens as f64 / 1_000_000.0 * model.input_price_per_million,
+            })
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
 
-    TokenEstimate {
-        total_tokens: gpt4_estimate,
+    Ok(TokenEstimate {
+        total_tokens: models.first().map_or(0, |m| m.tokens),
         char_count,
         word_count,
         line_count,
-        gpt4_estimate,
-        claude_estimate,
-        gemini_estimate,
+        models,
+    })
+}
+
+/// Build the tokenizer described by a registry entry
+pub fn model_tokenizer(model: &ModelConfig) -> Result<Box<dyn Tokenizer>, AppError> {
+    match (&model.tokenizer, model.chars_per_token) {
+        (Some(encoding), _) => {
+            let bpe = bpe_tokenizer(encoding).ok_or_else(|| {
+                AppError::invalid(format!("Unknown tokenizer for {}: {}", model.id, encoding))
+            })?;
+            Ok(match model.scale {
+                Some(scale) => Box::new(ScaledTokenizer::new(bpe, scale)),
+                None => Box::new(bpe),
+            })
+        }
+        (None, Some(ratio)) if ratio > 0.0 => Ok(Box::new(RatioTokenizer::new(ratio))),
+        _ => Err(AppError::invalid(format!(
+            "Model {} needs a tokenizer or a chars_per_token ratio",
+            model.id
+        ))),
     }
 }
 
 /// Batch estimate tokens for multiple files
 #[allow(dead_code)]
-pub fn estimate_tokens_batch(files: &[(String, String)]) -> Vec<(String, TokenEstimate)> {
+pub fn estimate_tokens_batch(
+    files: &[(String, String)],
+    models: &[ModelConfig],
+) -> Result<Vec<(String, TokenEstimate)>, AppError> {
     files
         .iter()
-        .map(|(path, content)| (path.clone(), estimate_tokens(content)))
+        .map(|(path, content)| Ok((path.clone(), estimate_tokens(content, models)?)))
         .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ModelRegistry;
 
     #[test]
     fn test_token_estimation() {
         let text = "Hello world! This is a test.";
-        let estimate = estimate_tokens(text);
+        let estimate = estimate_tokens(text, &ModelRegistry::default().models).unwrap();
 
         assert!(estimate.total_tokens > 0);
         assert!(estimate.char_count == 28);
         assert!(estimate.word_count == 6);
+        assert_eq!(estimate.models[0].model, "gpt4");
+        assert_eq!(estimate.models[0].tokens, 8);
     }
 
     #[test]
     fn test_empty_string() {
-        let estimate = estimate_tokens("");
-        assert_eq!(estimate.total_tokens, 1); // Minimum 1 token
+        let estimate = estimate_tokens("", &ModelRegistry::default().models).unwrap();
+        assert_eq!(estimate.total_tokens, 0);
+        assert!(estimate.models[0].exact && !estimate.models[2].exact);
+    }
+
+    #[test]
+    fn test_window_and_cost() {
+        let model = ModelConfig {
+            id: "local".to_string(),
+            name: "Local".to_string(),
+            tokenizer: None,
+            scale: None,
+            chars_per_token: Some(1.0),
+            context_window: 400,
+            input_price_per_million: 2.0,
+        };
+        let estimate = estimate_tokens(&"x".repeat(100), &[model]).unwrap();
+
+        assert_eq!(estimate.models[0].tokens, 100);
+        assert_eq!(estimate.models[0].context_used_percent, 25.0);
+        assert_eq!(estimate.models[0].cost, 0.0002);
     }
 }
@@ -0,0 +1,143 @@
+use tiktoken_rs::{cl100k_base_singleton, o200k_base_singleton, CoreBPE};
+
+/// Counts tokens for one encoding
+pub trait Tokenizer: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn count(&self, text: &str) -> u32;
+
+    /// Whether counts match what the model itself would report
+    fn is_exact(&self) -> bool;
+}
+
+/// Byte-pair encoding with a vocabulary bundled into the binary
+pub struct BpeTokenizer {
+    name: &'static str,
+    bpe: &'static CoreBPE,
+}
+
+impl Tokenizer for BpeTokenizer {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn count(&self, text: &str) -> u32 {
+        // Special token markers in file contents are plain text to the model
+        self.bpe.encode_ordinary(text).len() as u32
+    }
+
+    fn is_exact(&self) -> bool {
+        true
+    }
+}
+
+/// Approximate counts for models without a public tokenizer, derived from
+/// another tokenizer by a fixed factor
+pub struct ScaledTokenizer<T: Tokenizer> {
+    name: String,
+    base: T,
+    factor: f32,
+}
+
+impl<T: Tokenizer> ScaledTokenizer<T> {
+    pub fn new(base: T, factor: f32) -> Self {
+        Self {
+            name: format!("{} x{}", base.name(), factor),
+            base,
+            factor,
+        }
+    }
+
+    /// Scale a count already produced by the base tokenizer
+    pub fn scale(&self, base_tokens: u32) -> u32 {
+        (base_tokens as f32 * self.factor).round() as u32
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for ScaledTokenizer<T> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count(&self, text: &str) -> u32 {
+        self.scale(self.base.count(text))
+    }
+
+    fn is_exact(&self) -> bool {
+        false
+    }
+}
+
+/// Approximate counts from a characters-per-token ratio, for models whose
+/// tokenizer is not known at all
+pub struct RatioTokenizer {
+    name: String,
+    chars_per_token: f32,
+}
+
+impl RatioTokenizer {
+    pub fn new(chars_per_token: f32) -> Self {
+        Self {
+            name: format!("{} chars/token", chars_per_token),
+            chars_per_token,
+        }
+    }
+}
+
+impl Tokenizer for RatioTokenizer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count(&self, text: &str) -> u32 {
+        (text.chars().count() as f32 / self.chars_per_token).ceil() as u32
+    }
+
+    fn is_exact(&self) -> bool {
+        false
+    }
+}
+
+/// Look up a bundled BPE encoding by name ("cl100k_base" or "o200k_base")
+pub fn bpe_tokenizer(encoding: &str) -> Option<BpeTokenizer> {
+    match encoding {
+        "cl100k_base" => Some(BpeTokenizer {
+            name: "cl100k_base",
+            bpe: cl100k_base_singleton(),
+        }),
+        "o200k_base" => Some(BpeTokenizer {
+            name: "o200k_base",
+            bpe: o200k_base_singleton(),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bpe_counts() {
+        let cl100k = bpe_tokenizer("cl100k_base").unwrap();
+        let o200k = bpe_tokenizer("o200k_base").unwrap();
+
+        assert_eq!(cl100k.count("hello world"), 2);
+        assert_eq!(o200k.count("hello world"), 2);
+        assert_eq!(cl100k.count(""), 0);
+        assert_eq!(cl100k.count("<|endoftext|>"), 7);
+        assert!(bpe_tokenizer("unknown").is_none());
+    }
+
+    #[test]
+    fn test_scaled_is_approximate() {
+        let scaled = ScaledTokenizer::new(bpe_tokenizer("cl100k_base").unwrap(), 2.0);
+        assert_eq!(scaled.count("hello world"), 4);
+        assert!(!scaled.is_exact());
+        assert_eq!(scaled.name(), "cl100k_base x2");
+
+        let ratio = RatioTokenizer::new(4.0);
+        assert_eq!(ratio.count("hello world"), 3);
+        assert!(!ratio.is_exact());
+    }
+}
@@ -0,0 +1,374 @@
+use notify_debouncer_full::notify::event::{ModifyKind, RenameMode};
+use notify_debouncer_full::notify::{EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{
+    new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer, RecommendedCache,
+};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use walkdir::WalkDir;
+
+use crate::error::{AppError, ErrorKind};
+use crate::file_ops::{node_for_path, FileNode};
+use crate::ignore_rules::IgnoreRules;
+
+// How long filesystem notifications are collected before a diff is emitted
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// A single change to the workspace tree
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TreeChange {
+    Added { node: FileNode },
+    Removed { path: String },
+    Modified { node: FileNode },
+    Renamed { from: String, node: FileNode },
+}
+
+/// A batch of changes, emitted as a `tree-changed` event
+#[derive(Debug, Serialize, Clone)]
+pub struct TreeChanged {
+    pub root: String,
+    pub changes: Vec<TreeChange>,
+}
+
+type ActiveWatcher = Debouncer<RecommendedWatcher, RecommendedCache>;
+
+/// The debouncer of one workspace watch, shared with its event handler so
+/// directories can be watched as they appear
+type Session = Arc<Mutex<Option<ActiveWatcher>>>;
+
+/// Watcher for the open workspace root, managed as Tauri state
+#[derive(Default)]
+pub struct WorkspaceWatcher {
+    active: Mutex<Option<Session>>,
+}
+
+impl WorkspaceWatcher {
+    /// Start watching `root`, replacing any previous workspace watch.
+    ///
+    /// Each visible directory is watched on its own so that ignored ones, such
+    /// as `node_modules`, do not use up the system's watch limit.
+    pub fn watch<F>(
+        &self,
+        root: &str,
+        custom_patterns: &[String],
+        on_change: F,
+    ) -> Result<(), AppError>
+    where
+        F: Fn(TreeChanged) + Send + 'static,
+    {
+        let root_path = PathBuf::from(root);
+        if !root_path.is_dir() {
+            return Err(AppError::not_found("Directory does not exist").with_path(root));
+        }
+
+        let mut rules = IgnoreRules::new(&root_path, custom_patterns)?;
+        let patterns = custom_patterns.to_vec();
+        let handler_root = root_path.clone();
+        let dirs = watched_dirs(&root_path, &rules);
+        let mut watched: HashSet<PathBuf> = dirs.iter().cloned().collect();
+        let session = Session::default();
+        let handler_session = session.clone();
+
+        let mut debouncer = new_debouncer(
+            DEBOUNCE_TIMEOUT,
+            None,
+            move |result: DebounceEventResult| {
+                let events = match result {
+                    Ok(events) => events,
+                    Err(_) => return,
+                };
+
+                // Edited ignore files change what the tree should show
+                if events.iter().any(touches_ignore_file) {
+                    if let Ok(updated) = IgnoreRules::new(&handler_root, &patterns) {
+                        rules = updated;
+                        let dirs = watched_dirs(&handler_root, &rules);
+                        watch_new(&handler_session, &mut watched, dirs);
+                    }
+                }
+
+                let changes = changes_from_events(&events, &rules);
+                let mut added = Vec::new();
+                for change in &changes {
+                    match change {
+                        TreeChange::Added { node } => node_dirs(node, &mut added),
+                        TreeChange::Renamed { from, node } => {
+                            watched.retain(|dir| !dir.starts_with(from));
+                            node_dirs(node, &mut added);
+                        }
+                        TreeChange::Removed { path } => {
+                            watched.retain(|dir| !dir.starts_with(path));
+                        }
+                        TreeChange::Modified { .. } => {}
+                    }
+                }
+                watch_new(&handler_session, &mut watched, added);
+
+                if !changes.is_empty() {
+                    on_change(TreeChanged {
+                        root: handler_root.to_string_lossy().to_string(),
+                        changes,
+                    });
+                }
+            },
+        )
+        .map_err(|e| AppError::internal(format!("Failed to start file watcher: {}", e)))?;
+
+        // Subdirectories removed since the walk are skipped; the root must work
+        for dir in &dirs {
+            match debouncer.watch(dir, RecursiveMode::NonRecursive) {
+                Err(e) if *dir == root_path => {
+                    return Err(AppError::new(
+                        ErrorKind::Io,
+                        format!("Failed to watch directory: {}", e),
+                    )
+                    .with_path(dir))
+                }
+                _ => {}
+            }
+        }
+
+        *session
+            .lock()
+            .map_err(|_| AppError::internal("File watcher is unavailable"))? = Some(debouncer);
+        let previous = self
+            .active
+            .lock()
+            .map_err(|_| AppError::internal("File watcher is unavailable"))?
+            .replace(session);
+        stop(previous);
+
+        Ok(())
+    }
+
+    pub fn unwatch(&self) {
+        if let Ok(mut active) = self.active.lock() {
+            stop(active.take());
+        }
+    }
+}
+
+fn stop(session: Option<Session>) {
+    let debouncer = session.and_then(|session| session.lock().ok()?.take());
+    if let Some(debouncer) = debouncer {
+        debouncer.stop_nonblocking();
+    }
+}
+
+/// `root` and every directory below it that is not ignored
+fn watched_dirs(root: &Path, rules: &IgnoreRules) -> Vec<PathBuf> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || rules.matched_path(entry.path(), true).is_none()
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir())
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Directories in a node the tree just gained, which need watches of their own
+fn node_dirs(node: &FileNode, dirs: &mut Vec<PathBuf>) {
+    if node.is_dir {
+        dirs.push(PathBuf::from(&node.path));
+        for child in node.children.iter().flatten() {
+            node_dirs(child, dirs);
+        }
+    }
+}
+
+fn watch_new(session: &Session, watched: &mut HashSet<PathBuf>, dirs: Vec<PathBuf>) {
+    let Ok(mut active) = session.lock() else {
+        return;
+    };
+    let Some(debouncer) = active.as_mut() else {
+        return;
+    };
+    for dir in dirs {
+        if watched.insert(dir.clone()) {
+            let _ = debouncer.watch(&dir, RecursiveMode::NonRecursive);
+        }
+    }
+}
+
+fn touches_ignore_file(event: &DebouncedEvent) -> bool {
+    event.paths.iter().any(|p| {
+        matches!(
+            p.file_name().and_then(|n| n.to_str()),
+            Some(".gitignore") | Some(".ignore")
+        )
+    })
+}
+
+/// A path that no longer exists cannot tell whether it was a directory, so
+/// it counts as ignored when a rule for either kind matches
+fn is_ignored(path: &Path, rules: &IgnoreRules) -> bool {
+    match path.symlink_metadata() {
+        Ok(metadata) => rules.matched_path(path, metadata.is_dir()).is_some(),
+        Err(_) => {
+            rules.matched_path(path, true).is_some() || rules.matched_path(path, false).is_some()
+        }
+    }
+}
+
+/// Turn debounced filesystem events into tree changes, dropping ignored paths
+pub fn changes_from_events(events: &[DebouncedEvent], rules: &IgnoreRules) -> Vec<TreeChange> {
+    let mut changes = Vec::new();
+    let mut modified = HashSet::new();
+
+    let added = |path: &Path, changes: &mut Vec<TreeChange>| {
+        if let Some(node) = node_for_path(path, rules) {
+            changes.push(TreeChange::Added { node });
+        }
+    };
+    let removed = |path: &Path, changes: &mut Vec<TreeChange>| {
+        if !is_ignored(path, rules) {
+            changes.push(TreeChange::Removed {
+                path: path.to_string_lossy().to_string(),
+            });
+        }
+    };
+
+    for event in events {
+        match event.kind {
+            EventKind::Create(_) => {
+                for path in &event.paths {
+                    added(path, &mut changes);
+                }
+            }
+            EventKind::Remove(_) => {
+                for path in &event.paths {
+                    removed(path, &mut changes);
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                let (from, to) = (&event.paths[0], &event.paths[1]);
+                match (is_ignored(from, rules), node_for_path(to, rules)) {
+                    (false, Some(node)) => changes.push(TreeChange::Renamed {
+                        from: from.to_string_lossy().to_string(),
+                        node,
+                    }),
+                    (false, None) => removed(from, &mut changes),
+                    (true, Some(node)) => changes.push(TreeChange::Added { node }),
+                    (true, None) => {}
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(_)) => {
+                for path in &event.paths {
+                    if path.exists() {
+                        added(path, &mut changes);
+                    } else {
+                        removed(path, &mut changes);
+                    }
+                }
+            }
+            EventKind::Modify(_) => {
+                for path in &event.paths {
+                    if !path.is_file() || !modified.insert(path.clone()) {
+                        continue;
+                    }
+                    if let Some(node) = node_for_path(path, rules) {
+                        changes.push(TreeChange::Modified { node });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify_debouncer_full::notify::event::{CreateKind, RemoveKind};
+    use notify_debouncer_full::notify::Event;
+    use std::fs;
+    use std::time::Instant;
+
+    fn event(kind: EventKind, paths: &[&Path]) -> DebouncedEvent {
+        let mut event = Event::new(kind);
+        for path in paths {
+            event = event.add_path(path.to_path_buf());
+        }
+        DebouncedEvent::new(event, Instant::now())
+    }
+
+    #[test]
+    fn test_changes_skip_ignored_paths() {
+        let root = std::env::temp_dir().join(format!("filesurfer_watch_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("node_modules")).unwrap();
+        fs::write(root.join("new.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("renamed.rs"), "").unwrap();
+        fs::write(root.join("node_modules").join("dep.js"), "").unwrap();
+
+        let rules = IgnoreRules::new(&root, &[]).unwrap();
+        let events = vec![
+            event(EventKind::Create(CreateKind::File), &[&root.join("new.rs")]),
+            event(
+                EventKind::Create(CreateKind::File),
+                &[&root.join("node_modules").join("dep.js")],
+            ),
+            event(
+                EventKind::Remove(RemoveKind::File),
+                &[&root.join("gone.rs")],
+            ),
+            event(
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                &[&root.join("old.rs"), &root.join("renamed.rs")],
+            ),
+        ];
+
+        let changes = changes_from_events(&events, &rules);
+        assert_eq!(changes.len(), 3);
+        assert!(matches!(&changes[0], TreeChange::Added { node } if node.name == "new.rs"));
+        assert!(matches!(&changes[1], TreeChange::Removed { path } if path.ends_with("gone.rs")));
+        assert!(
+            matches!(&changes[2], TreeChange::Renamed { from, node } if from.ends_with("old.rs") && node.name == "renamed.rs")
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+    #[test]
+    fn test_ignored_directories_are_not_watched_or_reported() {
+        let root =
+            std::env::temp_dir().join(format!("filesurfer_watch_dirs_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src").join("nested")).unwrap();
+        fs::create_dir_all(root.join("build").join("out")).unwrap();
+        fs::create_dir_all(root.join("node_modules").join("dep")).unwrap();
+
+        let rules = IgnoreRules::new(&root, &["build/".to_string()]).unwrap();
+        let mut dirs = watched_dirs(&root, &rules);
+        dirs.sort();
+        assert_eq!(
+            dirs,
+            vec![
+                root.clone(),
+                root.join("src"),
+                root.join("src").join("nested")
+            ]
+        );
+
+        // Once removed, a directory only matches through its directory rules
+        fs::remove_dir_all(root.join("build")).unwrap();
+        let events = vec![
+            event(EventKind::Remove(RemoveKind::Any), &[&root.join("build")]),
+            event(EventKind::Remove(RemoveKind::Any), &[&root.join("gone")]),
+        ];
+        let changes = changes_from_events(&events, &rules);
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(&changes[0], TreeChange::Removed { path } if path.ends_with("gone")));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,13 +1,15 @@
 import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import {
   FolderOpen, Search, Settings, Moon, Sun, Copy, FileText,
   Code, History, Filter, X, Menu, ChevronRight, ChevronDown,
-  FileCode, Layers, Zap, AlertCircle, Command
+  FileCode, Layers, Zap, AlertCircle, Command, GitBranch
 } from "lucide-react";
 import { useDebounce } from "use-debounce";
 import { minimatch } from "minimatch";
 
+import { describeError } from "./errors";
 import DirectoryPicker from "./components/DirectoryPicker";
 import FileTree from "./components/FileTree";
 import PreviewPanel from "./components/PreviewPanel";
@@ -18,7 +20,10 @@ import KeyboardShortcuts, { KeyboardHelp } from "./components/KeyboardShortcuts"
 import TokenCounter from "./components/TokenCounter";
 import CodeAnalysisPanel from "./components/CodeAnalysisPanel";
 import ClipboardHistoryPanel from "./components/ClipboardHistoryPanel";
+import ChunksPanel from "./components/ChunksPanel";
 import InverseSelectionPanel from "./components/InverseSelectionPanel";
+import ChangedFilesPanel from "./components/ChangedFilesPanel";
+import ImportGraphPanel from "./components/ImportGraphPanel";
 import ProjectTypeDetector from "./components/ProjectTypeDetector";
 import TitleBar from "./components/TitleBar";
 
@@ -27,11 +32,20 @@ function App() {
   const [selectedPath, setSelectedPath] = useState("");
   const [fileTree, setFileTree] = useState([]);
   const [selectedFiles, setSelectedFiles] = useState([]);
+  // Files kept in full when skeleton mode is on
+  const [focusFiles, setFocusFiles] = useState([]);
+  // Symbols picked in the analysis panel, copied instead of their whole files
+  const [selections, setSelections] = useState([]);
   const [loading, setLoading] = useState(false);
   const [status, setStatus] = useState({ type: '', message: '' });
   const [previewContent, setPreviewContent] = useState("");
+  const [secretFindings, setSecretFindings] = useState([]);
+  // The scan mode the backend applied to the preview
+  const [secretMode, setSecretMode] = useState(null);
   const [previewLoading, setPreviewLoading] = useState(false);
   const [expandedNodes, setExpandedNodes] = useState([]);
+  const [jobProgress, setJobProgress] = useState(null);
+  const activeJobRef = useRef(null);
 
   // Search & Filter state
   const [searchQuery, setSearchQuery] = useState("");
@@ -49,7 +63,10 @@ function App() {
   const [showSettings, setShowSettings] = useState(false);
   const [showKeyboardHelp, setShowKeyboardHelp] = useState(false);
   const [showHistory, setShowHistory] = useState(false);
+  const [showChunks, setShowChunks] = useState(false);
   const [showInverseSelection, setShowInverseSelection] = useState(false);
+  const [showChangedFiles, setShowChangedFiles] = useState(false);
+  const [showImportGraph, setShowImportGraph] = useState(false);
 
   const searchInputRef = useRef(null);
 
@@ -93,6 +110,56 @@ function App() {
     }
   }, [selectedPath, selectedFiles, searchQuery, selectedExtensions, expandedNodes]);
 
+  // Keep the tree in sync with the filesystem while a workspace is open
+  useEffect(() => {
+    if (!selectedPath) return;
+
+    invoke("watch_workspace", {
+      root: selectedPath,
+      customPatterns: config?.custom_ignore_patterns || []
+    }).catch((error) => console.warn("File watcher unavailable:", error));
+
+    const unlisten = listen("tree-changed", (event) => {
+      if (event.payload.root !== selectedPath) return;
+      const changes = event.payload.changes;
+      setFileTree(prev => applyTreeChanges(prev, changes));
+
+      const removed = new Set(changes.filter(c => c.type === "removed").map(c => c.path));
+      const renamed = new Map(changes.filter(c => c.type === "renamed").map(c => [c.from, c.node.path]));
+      setSelectedFiles(prev => {
+        const deleted = prev.filter(path => removed.has(path));
+        if (deleted.length > 0) {
+          setStatus({ type: 'error', message: `${deleted.length} selected file(s) were deleted` });
+        }
+        // Deleted files stay selected so the output flags them instead of dropping them
+        return renamed.size > 0 ? prev.map(path => renamed.get(path) || path) : prev;
+      });
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+      invoke("unwatch_workspace").catch(() => {});
+    };
+  }, [selectedPath, config?.custom_ignore_patterns]);
+
+  // Selections pushed by editor plugins and scripts through the HTTP API
+  useEffect(() => {
+    const unlisten = listen("selection-pushed", async (event) => {
+      const { base_path, files } = event.payload;
+      const basePath = base_path || selectedPath;
+      if (basePath !== selectedPath) {
+        await handlePathSelect(basePath);
+      }
+      setSelectedFiles(files);
+      generatePreview(files, basePath);
+      setStatus({ type: 'success', message: `Received ${files.length} files from the HTTP API` });
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [selectedPath, config]);
+
   // Filter tree logic
   useEffect(() => {
     if (!fileTree || fileTree.length === 0) {
@@ -124,7 +191,8 @@ function App() {
         output_format: "markdown",
         git_only_mode: false,
         include_comments: true,
-        show_token_count: true
+        show_token_count: true,
+        lazy_loading: false
       };
       setConfig(defaultConfig);
       // Auto-repair: save the default config to fix the missing fields
@@ -136,12 +204,38 @@ function App() {
     try {
       await invoke("save_app_config", { config: newConfig });
       setConfig(newConfig);
+      if (newConfig.http_api_enabled !== config?.http_api_enabled
+        || newConfig.http_api_port !== config?.http_api_port) {
+        await syncHttpApi(newConfig);
+      }
     } catch (error) {
       console.error("Failed to save config:", error);
     }
   }
 
+  async function syncHttpApi(newConfig) {
+    try {
+      if (newConfig.http_api_enabled) {
+        const info = await invoke("start_http_api");
+        setStatus({ type: 'success', message: `HTTP API listening on 127.0.0.1:${info.port}` });
+        // Starting may have generated the access token
+        loadAppConfig();
+      } else {
+        await invoke("stop_http_api");
+      }
+    } catch (error) {
+      setStatus({ type: 'error', message: `HTTP API: ${describeError(error)}` });
+    }
+  }
+
   async function handlePathSelect(path) {
+    try {
+      // Every other command is confined to the open workspace
+      await invoke("open_workspace", { path });
+    } catch (error) {
+      setStatus({ type: 'error', message: `Cannot open ${path}: ${describeError(error)}` });
+      return;
+    }
     setSelectedPath(path);
     try {
       await invoke("add_recent", { path });
@@ -165,6 +259,47 @@ function App() {
     await scanDirectory(path);
   }
 
+  // Run a cancellable backend job and resolve with its result once it finishes
+  async function runJob(command, args) {
+    const jobId = `${command}-${Date.now()}-${Math.random().toString(36).slice(2, 8)}`;
+    const unlistenProgress = await listen("job-progress", (event) => {
+      if (event.payload.job_id === jobId) setJobProgress(event.payload);
+    });
+
+    return new Promise((resolve, reject) => {
+      let unlistenFinished = null;
+      const cleanup = () => {
+        unlistenProgress();
+        if (unlistenFinished) unlistenFinished();
+        if (activeJobRef.current === jobId) activeJobRef.current = null;
+        setJobProgress(null);
+      };
+
+      listen("job-finished", (event) => {
+        if (event.payload.job_id !== jobId) return;
+        cleanup();
+        if (event.payload.error) reject(event.payload.error);
+        else resolve(event.payload.result);
+      }).then((unlisten) => {
+        unlistenFinished = unlisten;
+        activeJobRef.current = jobId;
+        invoke(command, { jobId, ...args }).catch((error) => {
+          cleanup();
+          reject(error);
+        });
+      });
+    });
+  }
+
+  async function cancelActiveJob() {
+    if (!activeJobRef.current) return;
+    try {
+      await invoke("cancel_job", { jobId: activeJobRef.current });
+    } catch (error) {
+      console.error("Failed to cancel job:", error);
+    }
+  }
+
   async function scanDirectory(path) {
     setLoading(true);
     setStatus({ type: '', message: '' });
@@ -178,21 +313,23 @@ function App() {
       if (config?.git_only_mode) {
         try {
           const gitFiles = await invoke("get_git_files", { repoPath: path });
-          tree = await invoke("scan_dir", { path, customPatterns });
+          tree = await runJob("start_scan", { path, customPatterns });
           tree = filterTreeByGitFiles(tree, gitFiles);
         } catch (gitError) {
           console.warn("Git mode failed, fallback:", gitError);
-          tree = await invoke("scan_dir", { path, customPatterns });
+          tree = await runJob("start_scan", { path, customPatterns });
         }
+      } else if (config?.lazy_loading) {
+        tree = await invoke("scan_dir_lazy", { path, customPatterns });
       } else {
-        tree = await invoke("scan_dir", { path, customPatterns });
+        tree = await runJob("start_scan", { path, customPatterns });
       }
 
       setFileTree(tree);
       setStatus({ type: 'success', message: `Scanned ${countFiles(tree)} files` });
     } catch (error) {
       console.error("Error scanning:", error);
-      setStatus({ type: 'error', message: `Error: ${error}` });
+      setStatus({ type: 'error', message: `Error: ${describeError(error)}` });
       setFileTree([]);
     } finally {
       setLoading(false);
@@ -271,6 +408,28 @@ function App() {
     generatePreview(newSelected);
   }
 
+  function toggleFocus(path) {
+    const newFocus = focusFiles.includes(path)
+      ? focusFiles.filter(p => p !== path)
+      : [...focusFiles, path];
+    setFocusFiles(newFocus);
+    generatePreview(selectedFiles, selectedPath, newFocus);
+  }
+
+  function toggleSymbolSelection(file, name) {
+    const picked = selections.some(s => s.file === file && s.name === name);
+    const newSelections = picked
+      ? selections.filter(s => !(s.file === file && s.name === name))
+      : [...selections, { file, kind: "symbol", name }];
+    setSelections(newSelections);
+    generatePreview(selectedFiles, selectedPath, focusFiles, newSelections);
+  }
+
+  function clearSymbolSelections() {
+    setSelections([]);
+    generatePreview(selectedFiles, selectedPath, focusFiles, []);
+  }
+
   function selectAll() {
     const allFiles = [];
     function collectFiles(nodes) {
@@ -289,7 +448,22 @@ function App() {
     setPreviewContent("");
   }
 
-  function toggleExpandedNode(nodePath) {
+  async function toggleExpandedNode(nodePath, node) {
+    // Lazily loaded folders fetch their children the first time they are opened
+    if (node && node.is_dir && !node.children && node.has_children) {
+      try {
+        const children = await invoke("expand_dir", {
+          root: selectedPath,
+          path: nodePath,
+          customPatterns: config?.custom_ignore_patterns || []
+        });
+        setFileTree(prev => insertChildren(prev, nodePath, children));
+      } catch (error) {
+        setStatus({ type: 'error', message: `Error: ${describeError(error)}` });
+        return;
+      }
+    }
+
     setExpandedNodes(prev => {
       if (prev.includes(nodePath)) {
         return prev.filter(path => path !== nodePath);
@@ -299,6 +473,67 @@ function App() {
     });
   }
 
+  function parentPath(path) {
+    return path.slice(0, Math.max(path.lastIndexOf('/'), path.lastIndexOf('\\')));
+  }
+
+  function isWithin(path, dirPath) {
+    return path === dirPath || (path.startsWith(dirPath) && "/\\".includes(path[dirPath.length]));
+  }
+
+  function sortNodes(nodes) {
+    return [...nodes].sort((a, b) => {
+      if (a.is_dir !== b.is_dir) return a.is_dir ? -1 : 1;
+      return a.name.toLowerCase().localeCompare(b.name.toLowerCase());
+    });
+  }
+
+  function removeNode(nodes, path) {
+    return nodes
+      .filter(node => node.path !== path)
+      .map(node => node.is_dir && node.children && isWithin(path, node.path)
+        ? { ...node, children: removeNode(node.children, path) }
+        : node);
+  }
+
+  function upsertNode(nodes, newNode) {
+    const parent = parentPath(newNode.path);
+    if (nodes.length === 0 || parentPath(nodes[0].path) === parent) {
+      return sortNodes([...nodes.filter(node => node.path !== newNode.path), newNode]);
+    }
+    return nodes.map(node => {
+      // Folders that were never loaded pick the change up when expanded
+      if (node.is_dir && node.children && isWithin(parent, node.path)) {
+        return { ...node, children: upsertNode(node.children, newNode), has_children: true };
+      }
+      return node;
+    });
+  }
+
+  function applyTreeChanges(tree, changes) {
+    let next = tree;
+    for (const change of changes) {
+      if (change.type === "removed") {
+        next = removeNode(next, change.path);
+      } else if (change.type === "renamed") {
+        next = upsertNode(removeNode(next, change.from), change.node);
+      } else {
+        next = upsertNode(next, change.node);
+      }
+    }
+    return next;
+  }
+
+  function insertChildren(nodes, dirPath, children) {
+    return nodes.map(node => {
+      if (node.path === dirPath) return { ...node, children };
+      if (node.is_dir && node.children && dirPath.startsWith(node.path)) {
+        return { ...node, children: insertChildren(node.children, dirPath, children) };
+      }
+      return node;
+    });
+  }
+
   function handleInverseSelection(patterns) {
     setInversePatterns(patterns);
 
@@ -335,25 +570,47 @@ function App() {
     });
   }
 
-  async function generatePreview(files = selectedFiles) {
-    if (files.length === 0) {
+  function handleChangedSelection(changedFiles, replace) {
+    const newSelection = replace
+      ? changedFiles
+      : [...selectedFiles, ...changedFiles.filter(path => !selectedFiles.includes(path))];
+    setSelectedFiles(newSelection);
+    generatePreview(newSelection);
+    setStatus({ type: 'success', message: `Selected ${changedFiles.length} changed files` });
+  }
+
+  async function generatePreview(files = selectedFiles, basePath = selectedPath, focus = focusFiles, picked = selections) {
+    if (files.length === 0 && picked.length === 0) {
       setPreviewContent("");
+      setSecretFindings([]);
       return;
     }
     setPreviewLoading(true);
     try {
-      const content = await invoke("read_files", {
+      const output = await runJob("start_read", {
         request: {
           files: files,
-          base_path: selectedPath,
+          base_path: basePath,
           format: outputFormat,
-          max_file_size_mb: config?.max_file_size_mb || 10
+          max_file_size_mb: config?.max_file_size_mb || 10,
+          ignore_patterns: config?.custom_ignore_patterns || [],
+          diff: config?.diff,
+          git_metadata: config?.git_metadata,
+          tree: config?.project_tree,
+          skeleton: config?.skeleton ? { focus_files: focus } : null,
+          selections: picked
         }
       });
-      setPreviewContent(content);
+      setPreviewContent(output.content);
+      setSecretFindings(output.findings);
+      setSecretMode(output.secret_mode);
+      if (output.findings.length > 0) {
+        const action = output.secret_mode === "redact" ? "Redacted" : "Found";
+        setStatus({ type: 'error', message: `${action} ${output.findings.length} possible secret(s) in the selection` });
+      }
     } catch (error) {
       console.error("Error generating preview:", error);
-      setStatus({ type: 'error', message: `Error: ${error}` });
+      setStatus({ type: 'error', message: `Error: ${describeError(error)}` });
     } finally {
       setPreviewLoading(false);
     }
@@ -361,6 +618,14 @@ function App() {
 
   async function copyToClipboard() {
     if (!previewContent) return;
+    if (secretMode === "block" && secretFindings.length > 0) {
+      const listed = secretFindings
+        .map((f) => `${f.path}:${f.line} (${f.rule}) ${f.preview}`)
+        .join("\n");
+      if (!window.confirm(`The selection looks like it contains secrets:\n\n${listed}\n\nCopy anyway?`)) {
+        return;
+      }
+    }
     setLoading(true);
     try {
       await invoke("copy_clipboard", { content: previewContent });
@@ -371,7 +636,7 @@ function App() {
       });
       setStatus({ type: 'success', message: `Copied ${selectedFiles.length} files!` });
     } catch (error) {
-      setStatus({ type: 'error', message: `Error: ${error}` });
+      setStatus({ type: 'error', message: `Error: ${describeError(error)}` });
     } finally {
       setLoading(false);
     }
@@ -413,6 +678,13 @@ function App() {
                   >
                     <Filter size={12} className="inline mr-1.5" /> Inverse
                   </button>
+                  <button
+                    className="flex-1 px-3 py-1.5 text-xs font-medium rounded-md bg-secondary text-secondary-foreground hover:bg-secondary/80 transition-colors"
+                    onClick={() => setShowChangedFiles(true)}
+                    title="Select files changed in git"
+                  >
+                    <GitBranch size={12} className="inline mr-1.5" /> Changed
+                  </button>
                   <button
                     className="flex-1 px-3 py-1.5 text-xs font-medium rounded-md bg-secondary text-secondary-foreground hover:bg-secondary/80 transition-colors"
                     onClick={selectAll}
@@ -433,8 +705,12 @@ function App() {
                     selectedFiles={selectedFiles}
                     onToggle={toggleSelection}
                     loading={loading}
+                    progress={jobProgress}
+                    onCancel={cancelActiveJob}
                     expandedNodes={expandedNodes}
                     onToggleExpand={toggleExpandedNode}
+                    focusFiles={focusFiles}
+                    onToggleFocus={config?.skeleton ? toggleFocus : null}
                   />
                 </div>
               </>
@@ -488,6 +764,14 @@ function App() {
                 {config?.theme === 'dark' ? <Sun size={20} /> : <Moon size={20} />}
               </button>
               <div className="h-6 w-px bg-border mx-2" />
+              <button
+                className="p-2 rounded-md hover:bg-secondary text-muted-foreground hover:text-foreground transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+                onClick={() => setShowChunks(true)}
+                disabled={!previewContent}
+                title="Split into Parts"
+              >
+                <Layers size={20} />
+              </button>
               <button
                 className="flex items-center gap-2 px-4 py-2 bg-primary text-primary-foreground rounded-lg hover:bg-primary/90 transition-colors font-medium shadow-sm disabled:opacity-50 disabled:cursor-not-allowed"
                 onClick={copyToClipboard}
@@ -545,6 +829,9 @@ function App() {
                 <CodeAnalysisPanel
                   selectedFiles={selectedFiles}
                   selectedPath={selectedPath}
+                  selections={selections}
+                  onToggleSymbol={toggleSymbolSelection}
+                  onClearSelections={clearSymbolSelections}
                 />
               )}
             </div>
@@ -571,6 +858,25 @@ function App() {
             <ClipboardHistoryPanel onClose={() => setShowHistory(false)} />
           )
         }
+        {
+          showChunks && (
+            <ChunksPanel
+              request={{
+                files: selectedFiles,
+                base_path: selectedPath,
+                format: outputFormat,
+                max_file_size_mb: config?.max_file_size_mb || 10,
+                ignore_patterns: config?.custom_ignore_patterns || [],
+                diff: config?.diff,
+                git_metadata: config?.git_metadata,
+                tree: config?.project_tree,
+                skeleton: config?.skeleton ? { focus_files: focusFiles } : null,
+                selections
+              }}
+              onClose={() => setShowChunks(false)}
+            />
+          )
+        }
         {
           showInverseSelection && (
             <InverseSelectionPanel
@@ -579,6 +885,26 @@ function App() {
             />
           )
         }
+        {
+          showChangedFiles && (
+            <ChangedFilesPanel
+              repoPath={selectedPath}
+              onApply={handleChangedSelection}
+              onClose={() => setShowChangedFiles(false)}
+            />
+          )
+        }
+        {
+          showImportGraph && (
+            <ImportGraphPanel
+              seeds={selectedFiles}
+              basePath={selectedPath}
+              selectedFiles={selectedFiles}
+              onApply={handleImportSelection}
+              onClose={() => setShowImportGraph(false)}
+            />
+          )
+        }
 
         <KeyboardShortcuts
           onSelectAll={selectAll}
@@ -611,40 +937,16 @@ function App() {
     </div>
   );
 
-  async function handleDetectImports() {
+  function handleDetectImports() {
     if (selectedFiles.length === 0) return;
+    setShowImportGraph(true);
+  }
 
-    setLoading(true);
-    setStatus({ type: 'info', message: 'Detecting imports...' });
-
-    try {
-      let allImports = [];
-      for (const file of selectedFiles) {
-        try {
-          const imports = await invoke("resolve_file_imports", { filePath: file });
-          allImports = [...allImports, ...imports];
-        } catch (err) {
-          console.warn(`Failed to resolve imports for ${file}:`, err);
-        }
-      }
-
-      // Filter out already selected files
-      const newImports = allImports.filter(path => !selectedFiles.includes(path));
-
-      if (newImports.length > 0) {
-        const newSelection = [...selectedFiles, ...newImports];
-        setSelectedFiles(newSelection);
-        generatePreview(newSelection);
-        setStatus({ type: 'success', message: `Added ${newImports.length} imported files` });
-      } else {
-        setStatus({ type: 'info', message: 'No new imports found' });
-      }
-    } catch (error) {
-      console.error("Error detecting imports:", error);
-      setStatus({ type: 'error', message: `Error: ${error}` });
-    } finally {
-      setLoading(false);
-    }
+  function handleImportSelection(importedFiles) {
+    const newSelection = [...selectedFiles, ...importedFiles];
+    setSelectedFiles(newSelection);
+    generatePreview(newSelection);
+    setStatus({ type: 'success', message: `Added ${importedFiles.length} imported files` });
   }
 
   function handleClearRecent() {
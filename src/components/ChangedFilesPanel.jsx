@@ -0,0 +1,156 @@
+import { useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { X, Check, GitBranch, RefreshCw } from "lucide-react";
+import { describeError } from "../errors";
+
+const SOURCES = [
+    { kind: "base", label: "Branch" },
+    { kind: "working_tree", label: "Unstaged" },
+    { kind: "staged", label: "Staged" },
+    { kind: "untracked", label: "Untracked" },
+];
+
+const STATUS_LETTERS = {
+    added: "A",
+    modified: "M",
+    deleted: "D",
+    renamed: "R",
+    copied: "C",
+    type_changed: "T",
+    untracked: "?",
+    conflicted: "U",
+};
+
+export default function ChangedFilesPanel({ repoPath, onApply, onClose }) {
+    const [kind, setKind] = useState("base");
+    const [base, setBase] = useState("main");
+    const [mergeBase, setMergeBase] = useState(true);
+    const [files, setFiles] = useState(null);
+    const [loading, setLoading] = useState(false);
+    const [error, setError] = useState("");
+
+    async function loadChanges() {
+        setLoading(true);
+        setError("");
+        try {
+            const source = kind === "base" ? { kind, base, merge_base: mergeBase } : { kind };
+            setFiles(await invoke("get_git_changes", { repoPath, source }));
+        } catch (error) {
+            setError(describeError(error));
+            setFiles(null);
+        } finally {
+            setLoading(false);
+        }
+    }
+
+    // Deleted files have nothing left to read
+    const selectable = (files || []).filter(f => f.status !== "deleted");
+
+    function handleApply(replace) {
+        onApply(selectable.map(f => f.path), replace);
+        onClose();
+    }
+
+    return (
+        <div className="fixed inset-0 z-50 flex items-center justify-center bg-background/80 backdrop-blur-sm animate-in fade-in duration-200" onClick={onClose}>
+            <div
+                className="w-full max-w-lg bg-card border border-border rounded-xl shadow-2xl overflow-hidden animate-in zoom-in-95 duration-200"
+                onClick={(e) => e.stopPropagation()}
+            >
+                <div className="flex justify-between items-center px-6 py-4 border-b border-border bg-secondary/20">
+                    <div className="flex items-center gap-2">
+                        <div className="p-1.5 bg-primary/10 rounded-md">
+                            <GitBranch size={18} className="text-primary" />
+                        </div>
+                        <h2 className="text-lg font-semibold">Changed Files</h2>
+                    </div>
+                    <button
+                        className="p-1.5 rounded-md hover:bg-destructive/10 hover:text-destructive text-muted-foreground transition-colors"
+                        onClick={onClose}
+                    >
+                        <X size={18} />
+                    </button>
+                </div>
+
+                <div className="p-6 space-y-4">
+                    <div className="flex gap-2">
+                        {SOURCES.map(source => (
+                            <button
+                                key={source.kind}
+                                className={`flex-1 px-3 py-1.5 text-xs font-medium rounded-md transition-colors ${kind === source.kind
+                                    ? 'bg-primary text-primary-foreground'
+                                    : 'bg-secondary text-secondary-foreground hover:bg-secondary/80'
+                                    }`}
+                                onClick={() => { setKind(source.kind); setFiles(null); }}
+                            >
+                                {source.label}
+                            </button>
+                        ))}
+                    </div>
+
+                    {kind === "base" && (
+                        <div className="flex items-center gap-3">
+                            <input
+                                value={base}
+                                onChange={(e) => setBase(e.target.value)}
+                                placeholder="main"
+                                className="flex-1 px-3 py-2 bg-secondary/30 border border-border rounded-lg text-sm font-mono focus:ring-2 focus:ring-primary/20 focus:border-primary outline-none transition-all"
+                            />
+                            <label className="flex items-center gap-2 text-sm text-foreground whitespace-nowrap">
+                                <input
+                                    type="checkbox"
+                                    checked={mergeBase}
+                                    onChange={(e) => setMergeBase(e.target.checked)}
+                                />
+                                From merge-base
+                            </label>
+                        </div>
+                    )}
+
+                    <button
+                        className="w-full flex items-center justify-center gap-2 px-4 py-2 text-sm font-medium bg-secondary hover:bg-secondary/80 text-secondary-foreground rounded-lg border border-border transition-colors disabled:opacity-50"
+                        onClick={loadChanges}
+                        disabled={loading || (kind === "base" && !base.trim())}
+                    >
+                        <RefreshCw size={14} className={loading ? "animate-spin" : ""} /> Load Changes
+                    </button>
+
+                    {error && <p className="text-sm text-destructive">{error}</p>}
+
+                    {files && (
+                        <div className="max-h-64 overflow-y-auto custom-scrollbar border border-border rounded-lg divide-y divide-border">
+                            {files.length === 0 && (
+                                <p className="px-3 py-2 text-sm text-muted-foreground">No changes</p>
+                            )}
+                            {files.map(file => (
+                                <div key={file.path} className="flex items-center gap-3 px-3 py-1.5 text-sm" title={file.status}>
+                                    <span className="w-4 font-mono text-xs text-muted-foreground">{STATUS_LETTERS[file.status]}</span>
+                                    <span className={`font-mono truncate ${file.status === "deleted" ? "line-through text-muted-foreground" : ""}`}>
+                                        {file.old_path ? `${file.old_path} → ${file.relative_path}` : file.relative_path}
+                                    </span>
+                                </div>
+                            ))}
+                        </div>
+                    )}
+                </div>
+
+                <div className="flex justify-end gap-3 px-6 py-4 border-t border-border bg-secondary/20">
+                    <button
+                        className="px-4 py-2 text-sm font-medium text-foreground bg-secondary hover:bg-secondary/80 rounded-lg transition-colors border border-border disabled:opacity-50"
+                        onClick={() => handleApply(false)}
+                        disabled={selectable.length === 0}
+                    >
+                        Add to Selection
+                    </button>
+                    <button
+                        className="flex items-center gap-2 px-4 py-2 text-sm font-medium text-primary-foreground bg-primary hover:bg-primary/90 rounded-lg transition-colors shadow-sm disabled:opacity-50"
+                        onClick={() => handleApply(true)}
+                        disabled={selectable.length === 0}
+                    >
+                        <Check size={16} /> Select {selectable.length} Files
+                    </button>
+                </div>
+            </div>
+        </div>
+    );
+}
@@ -0,0 +1,162 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Layers, X, Copy, Check, AlertTriangle } from "lucide-react";
+import { describeError } from "../errors";
+
+export default function ChunksPanel({ request, onClose }) {
+    const [models, setModels] = useState([]);
+    const [model, setModel] = useState("");
+    const [maxTokens, setMaxTokens] = useState(32000);
+    const [result, setResult] = useState(null);
+    const [loading, setLoading] = useState(false);
+    const [error, setError] = useState("");
+    const [copiedParts, setCopiedParts] = useState([]);
+
+    useEffect(() => {
+        loadModels();
+    }, []);
+
+    async function loadModels() {
+        try {
+            const registry = await invoke("load_models");
+            setModels(registry.models);
+            if (registry.models.length > 0) {
+                setModel(registry.models[0].id);
+            }
+        } catch (error) {
+            console.error("Failed to load models:", error);
+        }
+    }
+
+    async function splitSelection(secretsConfirmed = false) {
+        setLoading(true);
+        setError("");
+        setCopiedParts([]);
+        try {
+            const chunks = await invoke("chunk_files", {
+                request: {
+                    request: { ...request, secrets_confirmed: secretsConfirmed },
+                    model,
+                    max_tokens: Number(maxTokens)
+                }
+            });
+            setResult(chunks);
+        } catch (error) {
+            if (error?.kind === "secrets_found" && window.confirm(`${error.message}\n\nSplit anyway?`)) {
+                return splitSelection(true);
+            }
+            setError(describeError(error));
+            setResult(null);
+        } finally {
+            setLoading(false);
+        }
+    }
+
+    async function copyPart(chunk) {
+        try {
+            await invoke("copy_clipboard", { content: chunk.content });
+            setCopiedParts((parts) => [...parts, chunk.part]);
+        } catch (error) {
+            console.error("Failed to copy:", error);
+        }
+    }
+
+    function describeEntry(entry) {
+        return entry.line_start
+            ? `${entry.path} (lines ${entry.line_start}-${entry.line_end})`
+            : entry.path;
+    }
+
+    return (
+        <div className="fixed inset-0 z-50 flex items-center justify-center bg-background/80 backdrop-blur-sm animate-in fade-in duration-200" onClick={onClose}>
+            <div
+                className="w-full max-w-lg bg-card border border-border rounded-xl shadow-2xl overflow-hidden animate-in zoom-in-95 duration-200"
+                onClick={(e) => e.stopPropagation()}
+            >
+                <div className="flex justify-between items-center px-6 py-4 border-b border-border bg-secondary/20">
+                    <div className="flex items-center gap-2">
+                        <div className="p-1.5 bg-primary/10 rounded-md">
+                            <Layers size={18} className="text-primary" />
+                        </div>
+                        <h2 className="text-lg font-semibold">Split into Parts</h2>
+                    </div>
+                    <button
+                        className="p-1.5 rounded-md hover:bg-destructive/10 hover:text-destructive text-muted-foreground transition-colors"
+                        onClick={onClose}
+                    >
+                        <X size={18} />
+                    </button>
+                </div>
+
+                <div className="flex items-end gap-2 px-4 py-3 border-b border-border/50">
+                    <label className="flex flex-col gap-1 flex-1 text-xs text-muted-foreground">
+                        Model
+                        <select
+                            className="px-2 py-1.5 bg-background border border-border rounded-md text-sm text-foreground"
+                            value={model}
+                            onChange={(e) => setModel(e.target.value)}
+                        >
+                            {models.map((m) => (
+                                <option key={m.id} value={m.id}>{m.name}</option>
+                            ))}
+                        </select>
+                    </label>
+                    <label className="flex flex-col gap-1 w-32 text-xs text-muted-foreground">
+                        Tokens per part
+                        <input
+                            type="number"
+                            min="1"
+                            className="px-2 py-1.5 bg-background border border-border rounded-md text-sm text-foreground"
+                            value={maxTokens}
+                            onChange={(e) => setMaxTokens(e.target.value)}
+                        />
+                    </label>
+                    <button
+                        className="px-3 py-1.5 text-sm font-medium bg-primary text-primary-foreground hover:bg-primary/90 rounded-md shadow-sm transition-colors disabled:opacity-50"
+                        onClick={() => splitSelection()}
+                        disabled={loading || !model}
+                    >
+                        {loading ? "Splitting..." : "Split"}
+                    </button>
+                </div>
+
+                <div className="max-h-[60vh] overflow-y-auto custom-scrollbar p-4 space-y-3">
+                    {error && (
+                        <div className="text-xs text-destructive bg-destructive/10 p-2 rounded">{error}</div>
+                    )}
+                    {result?.chunks.map((chunk) => (
+                        <div key={chunk.part} className="bg-secondary/10 border border-border/50 rounded-lg p-3">
+                            <div className="flex justify-between items-center mb-2">
+                                <span className="text-sm font-medium">
+                                    Part {chunk.part} of {chunk.total_parts}
+                                </span>
+                                <div className="flex items-center gap-2">
+                                    <span className="text-xs font-mono text-muted-foreground">
+                                        {chunk.tokens.toLocaleString()} tokens
+                                    </span>
+                                    <button
+                                        className="flex items-center gap-1.5 px-3 py-1 text-xs font-medium bg-primary text-primary-foreground hover:bg-primary/90 rounded-md shadow-sm transition-colors"
+                                        onClick={() => copyPart(chunk)}
+                                    >
+                                        {copiedParts.includes(chunk.part) ? <Check size={12} /> : <Copy size={12} />} Copy
+                                    </button>
+                                </div>
+                            </div>
+                            <ul className="text-xs font-mono text-muted-foreground space-y-0.5">
+                                {chunk.files.map((entry, idx) => (
+                                    <li key={idx} className="truncate">{describeEntry(entry)}</li>
+                                ))}
+                            </ul>
+                            {chunk.over_limit && (
+                                <div className="flex items-start gap-2 mt-2 text-[10px] text-yellow-500 bg-yellow-500/10 p-2 rounded">
+                                    <AlertTriangle size={12} className="shrink-0 mt-0.5" />
+                                    <span>A single function is larger than the limit</span>
+                                </div>
+                            )}
+                        </div>
+                    ))}
+                </div>
+            </div>
+        </div>
+    );
+}
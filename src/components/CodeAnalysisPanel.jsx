@@ -1,10 +1,11 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Search, RefreshCw, Box, Code, CheckSquare, AlertCircle } from "lucide-react";
+import { Search, RefreshCw, Box, Code, CheckSquare, AlertCircle, ListTree, Scissors, X } from "lucide-react";
 
-export default function CodeAnalysisPanel({ selectedFiles, selectedPath }) {
+export default function CodeAnalysisPanel({ selectedFiles, selectedPath, selections = [], onToggleSymbol, onClearSelections }) {
     const [dependencies, setDependencies] = useState([]);
     const [functions, setFunctions] = useState([]);
+    const [outlines, setOutlines] = useState([]);
     const [todos, setTodos] = useState([]);
     const [loading, setLoading] = useState(false);
     const [activeTab, setActiveTab] = useState("dependencies");
@@ -15,6 +16,7 @@ export default function CodeAnalysisPanel({ selectedFiles, selectedPath }) {
         } else {
             setDependencies([]);
             setFunctions([]);
+            setOutlines([]);
             setTodos([]);
         }
     }, [selectedFiles]);
@@ -28,18 +30,25 @@ export default function CodeAnalysisPanel({ selectedFiles, selectedPath }) {
             const funcPromises = selectedFiles.map(file =>
                 invoke("get_functions", { filePath: file }).catch(() => [])
             );
+            const outlinePromises = selectedFiles.map(file =>
+                invoke("get_outline", { filePath: file })
+                    .then(symbols => ({ file, symbols }))
+                    .catch(() => ({ file, symbols: [] }))
+            );
             const todoPromises = selectedFiles.map(file =>
                 invoke("get_todos", { filePath: file }).catch(() => [])
             );
 
-            const [allDeps, allFuncs, allTodos] = await Promise.all([
+            const [allDeps, allFuncs, allOutlines, allTodos] = await Promise.all([
                 Promise.all(depPromises),
                 Promise.all(funcPromises),
+                Promise.all(outlinePromises),
                 Promise.all(todoPromises)
             ]);
 
             setDependencies(allDeps.flat());
             setFunctions(allFuncs.flat());
+            setOutlines(allOutlines.filter(outline => outline.symbols.length > 0));
             setTodos(allTodos.flat());
         } catch (error) {
             console.error("Code analysis failed:", error);
@@ -48,6 +57,8 @@ export default function CodeAnalysisPanel({ selectedFiles, selectedPath }) {
         }
     }
 
+    const isPicked = (file, name) => selections.some(s => s.file === file && s.name === name);
+
     if (selectedFiles.length === 0) {
         return (
             <div className="flex flex-col items-center justify-center h-full p-8 text-muted-foreground/50 border border-dashed border-border rounded-lg bg-secondary/10">
@@ -64,6 +75,15 @@ export default function CodeAnalysisPanel({ selectedFiles, selectedPath }) {
                     <Code size={16} className="text-primary" />
                     Code Analysis
                 </h4>
+                {selections.length > 0 && (
+                    <button
+                        className="flex items-center gap-1.5 px-2 py-1 ml-auto mr-2 text-xs font-medium text-primary bg-primary/10 hover:bg-primary/20 rounded-md transition-colors"
+                        onClick={onClearSelections}
+                        title="Copy whole files again"
+                    >
+                        <Scissors size={12} /> {selections.length} picked <X size={12} />
+                    </button>
+                )}
                 <button
                     className="flex items-center gap-1.5 px-2 py-1 text-xs font-medium bg-secondary hover:bg-secondary/80 text-secondary-foreground rounded-md transition-colors disabled:opacity-50"
                     onClick={analyzeCode}
@@ -87,6 +107,12 @@ export default function CodeAnalysisPanel({ selectedFiles, selectedPath }) {
                 >
                     <Code size={12} /> Funcs ({functions.length})
                 </button>
+                <button
+                    className={`flex-1 py-2 text-xs font-medium border-b-2 transition-colors flex items-center justify-center gap-1.5 ${activeTab === "outline" ? "border-primary text-primary bg-primary/5" : "border-transparent text-muted-foreground hover:text-foreground hover:bg-secondary/30"}`}
+                    onClick={() => setActiveTab("outline")}
+                >
+                    <ListTree size={12} /> Outline
+                </button>
                 <button
                     className={`flex-1 py-2 text-xs font-medium border-b-2 transition-colors flex items-center justify-center gap-1.5 ${activeTab === "todos" ? "border-primary text-primary bg-primary/5" : "border-transparent text-muted-foreground hover:text-foreground hover:bg-secondary/30"}`}
                     onClick={() => setActiveTab("todos")}
@@ -124,7 +150,10 @@ export default function CodeAnalysisPanel({ selectedFiles, selectedPath }) {
                             functions.map((func, idx) => (
                                 <div key={idx} className="p-3 hover:bg-secondary/20 transition-colors">
                                     <div className="flex items-center justify-between mb-1">
-                                        <span className="text-sm font-semibold text-foreground">{func.name}</span>
+                                        <span className="flex items-center gap-1.5 text-sm font-semibold text-foreground">
+                                            <PickButton picked={isPicked(func.file, func.name)} onClick={() => onToggleSymbol(func.file, func.name)} />
+                                            {func.name}
+                                        </span>
                                         <span className="text-[10px] text-muted-foreground font-mono">
                                             {getRelativePath(func.file, selectedPath)}:{func.line_start}-{func.line_end}
                                         </span>
@@ -138,6 +167,27 @@ export default function CodeAnalysisPanel({ selectedFiles, selectedPath }) {
                     </div>
                 )}
 
+                {activeTab === "outline" && (
+                    <div className="divide-y divide-border/50">
+                        {outlines.length === 0 ? (
+                            <div className="p-8 text-center text-xs text-muted-foreground">No symbols found</div>
+                        ) : (
+                            outlines.map(({ file, symbols }) => (
+                                <div key={file} className="p-3">
+                                    <div className="text-[10px] text-muted-foreground font-mono mb-1 truncate">
+                                        {getRelativePath(file, selectedPath)}
+                                    </div>
+                                    <SymbolList
+                                        symbols={symbols}
+                                        isPicked={(name) => isPicked(file, name)}
+                                        onToggle={(name) => onToggleSymbol(file, name)}
+                                    />
+                                </div>
+                            ))
+                        )}
+                    </div>
+                )}
+
                 {activeTab === "todos" && (
                     <div className="divide-y divide-border/50">
                         {todos.length === 0 ? (
@@ -167,6 +217,62 @@ export default function CodeAnalysisPanel({ selectedFiles, selectedPath }) {
     );
 }
 
+const KIND_LABELS = {
+    function: "fn",
+    method: "fn",
+    struct: "struct",
+    enum: "enum",
+    trait: "trait",
+    impl: "impl",
+    class: "class",
+    interface: "iface",
+    type_alias: "type",
+    module: "mod",
+    constant: "const",
+};
+
+function PickButton({ picked, onClick }) {
+    return (
+        <button
+            className={`p-0.5 rounded hover:bg-secondary transition-colors ${picked ? "text-primary" : "text-muted-foreground/50 hover:text-foreground"}`}
+            onClick={onClick}
+            title={picked ? "Copy the whole file again" : "Copy just this instead of the whole file"}
+        >
+            <Scissors size={12} />
+        </button>
+    );
+}
+
+function SymbolList({ symbols, isPicked, onToggle, depth = 0 }) {
+    return symbols.map((symbol, idx) => {
+        // Methods are picked by their qualified name so same-named ones stay apart
+        const name = symbol.parent ? `${symbol.parent}.${symbol.name}` : symbol.name;
+        return (
+            <div key={`${symbol.name}-${idx}`}>
+                <div
+                    className="flex items-center gap-2 py-0.5 hover:bg-secondary/20 rounded"
+                    style={{ paddingLeft: depth * 14 }}
+                    title={symbol.signature}
+                >
+                    <PickButton picked={isPicked(name)} onClick={() => onToggle(name)} />
+                    <span className="text-[10px] font-mono text-primary bg-primary/10 px-1 rounded w-10 text-center shrink-0">
+                        {KIND_LABELS[symbol.kind] || symbol.kind}
+                    </span>
+                    <span className={`text-xs font-medium truncate ${symbol.visibility === "private" ? "text-muted-foreground" : "text-foreground"}`}>
+                        {symbol.name}
+                    </span>
+                    <span className="text-[10px] text-muted-foreground font-mono ml-auto shrink-0">
+                        {symbol.line_start}-{symbol.line_end}
+                    </span>
+                </div>
+                {symbol.children && (
+                    <SymbolList symbols={symbol.children} isPicked={isPicked} onToggle={onToggle} depth={depth + 1} />
+                )}
+            </div>
+        );
+    });
+}
+
 function getRelativePath(fullPath, basePath) {
     if (!basePath) return fullPath;
     return fullPath.replace(basePath, "").replace(/^[\\\/]/, "");
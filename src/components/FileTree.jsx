@@ -1,12 +1,25 @@
 import { Folder, Loader2 } from "lucide-react";
 import FileTreeNode from "./FileTreeNode";
 
-export default function FileTree({ fileTree, selectedFiles, onToggle, loading, expandedNodes = [], onToggleExpand }) {
+export default function FileTree({ fileTree, selectedFiles, onToggle, loading, progress, onCancel, expandedNodes = [], onToggleExpand, focusFiles = [], onToggleFocus }) {
     if (loading) {
         return (
             <div className="flex flex-col items-center justify-center h-40 text-muted-foreground animate-in fade-in duration-300">
                 <Loader2 size={24} className="animate-spin mb-3 text-primary" />
                 <span className="text-sm font-medium">Scanning files...</span>
+                {progress && (
+                    <span className="text-xs text-muted-foreground/70 mt-1 max-w-full truncate px-4" title={progress.current_path}>
+                        {progress.files_visited} entries · {progress.current_path}
+                    </span>
+                )}
+                {onCancel && (
+                    <button
+                        className="mt-3 px-3 py-1 text-xs font-medium rounded-md border border-border hover:bg-secondary transition-colors"
+                        onClick={onCancel}
+                    >
+                        Cancel
+                    </button>
+                )}
             </div>
         );
     }
@@ -30,6 +43,8 @@ export default function FileTree({ fileTree, selectedFiles, onToggle, loading, e
                     onToggle={onToggle}
                     expandedNodes={expandedNodes}
                     onToggleExpand={onToggleExpand}
+                    focusFiles={focusFiles}
+                    onToggleFocus={onToggleFocus}
                     depth={0}
                 />
             ))}
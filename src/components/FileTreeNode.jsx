@@ -1,5 +1,5 @@
 import { useState, useEffect, useRef } from "react";
-import { ChevronRight, ChevronDown, Folder, File, FileCode, FileJson, FileText, Image, Check } from "lucide-react";
+import { ChevronRight, ChevronDown, Folder, File, FileCode, FileJson, FileText, Image, Check, Focus } from "lucide-react";
 
 function formatBytes(bytes) {
     if (bytes === 0) return '0 B';
@@ -38,7 +38,7 @@ function getFileIcon(filename) {
     }
 }
 
-export default function FileTreeNode({ node, selectedFiles, onToggle, depth = 0, expandedNodes = [], onToggleExpand }) {
+export default function FileTreeNode({ node, selectedFiles, onToggle, depth = 0, expandedNodes = [], onToggleExpand, focusFiles = [], onToggleFocus }) {
     const checkboxRef = useRef(null);
 
     // Use centralized expanded state instead of local state
@@ -74,10 +74,16 @@ export default function FileTreeNode({ node, selectedFiles, onToggle, depth = 0,
         onToggle(node);
     };
 
+    const focused = focusFiles.includes(node.path);
+    const handleFocus = (e) => {
+        e.stopPropagation();
+        onToggleFocus(node.path);
+    };
+
     const handleExpand = (e) => {
         e.stopPropagation();
-        if (node.is_dir && node.children && onToggleExpand) {
-            onToggleExpand(node.path);
+        if (node.is_dir && (node.children || node.has_children) && onToggleExpand) {
+            onToggleExpand(node.path, node);
         }
     };
 
@@ -144,6 +150,16 @@ export default function FileTreeNode({ node, selectedFiles, onToggle, depth = 0,
                     </span>
                 </div>
 
+                {!node.is_dir && onToggleFocus && isFullySelected && (
+                    <button
+                        className={`p-0.5 rounded-sm hover:bg-secondary transition-opacity ${focused ? 'text-primary' : 'text-muted-foreground opacity-0 group-hover:opacity-100'}`}
+                        onClick={handleFocus}
+                        title={focused ? "Focused: copied in full" : "Copy in full instead of as a skeleton"}
+                    >
+                        <Focus size={12} />
+                    </button>
+                )}
+
                 {!node.is_dir && (
                     <span className="text-[10px] text-muted-foreground/50 opacity-0 group-hover:opacity-100 transition-opacity whitespace-nowrap ml-2">
                         {formatBytes(node.size)}
@@ -161,6 +177,8 @@ export default function FileTreeNode({ node, selectedFiles, onToggle, depth = 0,
                             onToggle={onToggle}
                             expandedNodes={expandedNodes}
                             onToggleExpand={onToggleExpand}
+                            focusFiles={focusFiles}
+                            onToggleFocus={onToggleFocus}
                             depth={depth + 1}
                         />
                     ))}
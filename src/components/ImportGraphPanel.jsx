@@ -0,0 +1,159 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { X, Check, Network, RefreshCw, Package, AlertCircle } from "lucide-react";
+import { describeError } from "../errors";
+
+export default function ImportGraphPanel({ seeds, basePath, selectedFiles, onApply, onClose }) {
+    const [depth, setDepth] = useState(3);
+    const [graph, setGraph] = useState(null);
+    const [loading, setLoading] = useState(false);
+    const [error, setError] = useState("");
+
+    useEffect(() => {
+        loadGraph();
+    }, []);
+
+    async function loadGraph() {
+        setLoading(true);
+        setError("");
+        try {
+            setGraph(await invoke("get_import_graph", { seeds, maxDepth: depth }));
+        } catch (error) {
+            setError(describeError(error));
+            setGraph(null);
+        } finally {
+            setLoading(false);
+        }
+    }
+
+    const relative = (path) => basePath ? path.replace(basePath, "").replace(/^[\\\/]/, "") : path;
+
+    // Group files by how many imports away from the selection they are
+    const levels = [];
+    for (const node of graph?.nodes || []) {
+        (levels[node.depth] ||= []).push(node);
+    }
+    const importers = (path) => (graph?.edges || []).filter(e => e.to === path).map(e => relative(e.from));
+
+    const newFiles = (graph?.nodes || []).map(n => n.path).filter(path => !selectedFiles.includes(path));
+
+    function handleApply() {
+        onApply(newFiles);
+        onClose();
+    }
+
+    return (
+        <div className="fixed inset-0 z-50 flex items-center justify-center bg-background/80 backdrop-blur-sm animate-in fade-in duration-200" onClick={onClose}>
+            <div
+                className="w-full max-w-2xl bg-card border border-border rounded-xl shadow-2xl overflow-hidden animate-in zoom-in-95 duration-200"
+                onClick={(e) => e.stopPropagation()}
+            >
+                <div className="flex justify-between items-center px-6 py-4 border-b border-border bg-secondary/20">
+                    <div className="flex items-center gap-2">
+                        <div className="p-1.5 bg-primary/10 rounded-md">
+                            <Network size={18} className="text-primary" />
+                        </div>
+                        <h2 className="text-lg font-semibold">Import Graph</h2>
+                    </div>
+                    <button
+                        className="p-1.5 rounded-md hover:bg-destructive/10 hover:text-destructive text-muted-foreground transition-colors"
+                        onClick={onClose}
+                    >
+                        <X size={18} />
+                    </button>
+                </div>
+
+                <div className="p-6 space-y-4">
+                    <div className="flex items-center gap-3">
+                        <label className="text-sm text-foreground whitespace-nowrap">Follow imports</label>
+                        <input
+                            type="number"
+                            min="1"
+                            value={depth}
+                            onChange={(e) => setDepth(Math.max(1, parseInt(e.target.value) || 1))}
+                            className="w-20 px-3 py-2 bg-secondary/30 border border-border rounded-lg text-sm focus:ring-2 focus:ring-primary/20 focus:border-primary outline-none transition-all"
+                        />
+                        <span className="text-sm text-muted-foreground">levels deep</span>
+                        <button
+                            className="ml-auto flex items-center gap-2 px-4 py-2 text-sm font-medium bg-secondary hover:bg-secondary/80 text-secondary-foreground rounded-lg border border-border transition-colors disabled:opacity-50"
+                            onClick={loadGraph}
+                            disabled={loading}
+                        >
+                            <RefreshCw size={14} className={loading ? "animate-spin" : ""} /> Reload
+                        </button>
+                    </div>
+
+                    {error && <p className="text-sm text-destructive">{error}</p>}
+
+                    {graph && (
+                        <div className="max-h-80 overflow-y-auto custom-scrollbar border border-border rounded-lg divide-y divide-border">
+                            {levels.map((nodes, level) => nodes && (
+                                <div key={level} className="px-3 py-2">
+                                    <div className="text-[10px] font-semibold uppercase tracking-wider text-muted-foreground mb-1">
+                                        {level === 0 ? "Selected" : `Level ${level}`}
+                                    </div>
+                                    {nodes.map(node => (
+                                        <div key={node.path} className="flex items-center gap-2 py-0.5 text-sm" title={node.error?.message}>
+                                            <span className={`font-mono truncate ${selectedFiles.includes(node.path) ? "text-muted-foreground" : "text-foreground"}`}>
+                                                {relative(node.path)}
+                                            </span>
+                                            {!node.expanded && !node.error && (
+                                                <span className="text-[10px] text-muted-foreground shrink-0">imports not followed</span>
+                                            )}
+                                            {node.error && <AlertCircle size={12} className="text-destructive shrink-0" />}
+                                            {level > 0 && (
+                                                <span className="ml-auto text-[10px] text-muted-foreground font-mono truncate max-w-[40%]">
+                                                    ← {importers(node.path).join(", ")}
+                                                </span>
+                                            )}
+                                        </div>
+                                    ))}
+                                </div>
+                            ))}
+
+                            {graph.unresolved.length > 0 && (
+                                <div className="px-3 py-2">
+                                    <div className="text-[10px] font-semibold uppercase tracking-wider text-destructive mb-1">
+                                        Unresolved ({graph.unresolved.length})
+                                    </div>
+                                    {graph.unresolved.map(ref => (
+                                        <div key={`${ref.file}-${ref.dependency}`} className="text-xs font-mono text-muted-foreground truncate">
+                                            {ref.dependency} <span className="opacity-60">in {relative(ref.file)}:{ref.line_number}</span>
+                                        </div>
+                                    ))}
+                                </div>
+                            )}
+
+                            {graph.external.length > 0 && (
+                                <div className="px-3 py-2">
+                                    <div className="text-[10px] font-semibold uppercase tracking-wider text-muted-foreground mb-1 flex items-center gap-1">
+                                        <Package size={10} /> External ({graph.external.length})
+                                    </div>
+                                    <div className="text-xs font-mono text-muted-foreground">
+                                        {[...new Set(graph.external.map(ref => ref.dependency))].join(", ")}
+                                    </div>
+                                </div>
+                            )}
+                        </div>
+                    )}
+                </div>
+
+                <div className="flex justify-end gap-3 px-6 py-4 border-t border-border bg-secondary/20">
+                    <button
+                        className="px-4 py-2 text-sm font-medium text-foreground bg-secondary hover:bg-secondary/80 rounded-lg transition-colors border border-border"
+                        onClick={onClose}
+                    >
+                        Cancel
+                    </button>
+                    <button
+                        className="flex items-center gap-2 px-4 py-2 text-sm font-medium text-primary-foreground bg-primary hover:bg-primary/90 rounded-lg transition-colors shadow-sm disabled:opacity-50"
+                        onClick={handleApply}
+                        disabled={newFiles.length === 0}
+                    >
+                        <Check size={16} /> Add {newFiles.length} Files
+                    </button>
+                </div>
+            </div>
+        </div>
+    );
+}
@@ -1,6 +1,6 @@
 import { useEffect, useState } from "react";
 import SyntaxHighlighter from "./SyntaxHighlighter";
-import { Copy, Download, FileJson, FileCode, FileText, Edit2, Check, Loader2, Code, FileType } from "lucide-react";
+import { Copy, Download, FileJson, FileCode, FileText, Edit2, Check, Loader2, Code, FileType, GitCompare } from "lucide-react";
 import { invoke } from "@tauri-apps/api/core";
 
 export default function PreviewPanel({
@@ -93,6 +93,16 @@ export default function PreviewPanel({
                     >
                         <Code size={12} /> XML
                     </button>
+                    <button
+                        className={`px-3 py-1.5 text-xs font-medium rounded-md transition-all flex items-center gap-1.5 ${format === 'diff'
+                            ? 'bg-background text-foreground shadow-sm'
+                            : 'text-muted-foreground hover:text-foreground hover:bg-background/50'
+                            }`}
+                        onClick={() => onFormatChange('diff')}
+                        title="Changes since the base ref set in Settings"
+                    >
+                        <GitCompare size={12} /> Diff
+                    </button>
                 </div>
 
                 <div className="flex gap-2">
@@ -139,7 +149,7 @@ export default function PreviewPanel({
                     <div className="absolute inset-0 overflow-auto custom-scrollbar">
                         <SyntaxHighlighter
                             code={content}
-                            language={format === 'markdown' ? 'markdown' : format === 'json' ? 'json' : 'xml'}
+                            language={format === 'markdown' ? 'markdown' : format === 'json' ? 'json' : format === 'diff' ? 'diff' : 'xml'}
                         />
                     </div>
                 )}
@@ -1,4 +1,4 @@
-import { X, Save, RotateCcw, Settings, FileCode, Database, GitBranch, MessageSquare, Hash } from "lucide-react";
+import { X, Save, RotateCcw, Settings, FileCode, Database, GitBranch, MessageSquare, Hash, FolderTree, Globe, ShieldAlert, History, Braces } from "lucide-react";
 import { useState } from "react";
 
 export default function SettingsPanel({ config, onConfigChange, onClose }) {
@@ -8,6 +8,16 @@ export default function SettingsPanel({ config, onConfigChange, onClose }) {
         setLocalConfig({ ...localConfig, [key]: value });
     };
 
+    const handleSecretScanChange = (key, value) => {
+        handleChange("secret_scan", { ...localConfig.secret_scan, [key]: value });
+    };
+
+    const toggleSecretRule = (id) => {
+        handleSecretScanChange("rules", localConfig.secret_scan.rules.map(rule =>
+            rule.id === id ? { ...rule, enabled: !rule.enabled } : rule
+        ));
+    };
+
     const handleSave = () => {
         onConfigChange(localConfig);
         onClose();
@@ -22,7 +32,12 @@ export default function SettingsPanel({ config, onConfigChange, onClose }) {
             output_format: "markdown",
             git_only_mode: false,
             include_comments: true,
-            show_token_count: true
+            show_token_count: true,
+            lazy_loading: false,
+            http_api_enabled: false,
+            http_api_port: 7615,
+            http_api_token: localConfig.http_api_token,
+            allowed_paths: []
         });
     };
 
@@ -62,9 +77,113 @@ export default function SettingsPanel({ config, onConfigChange, onClose }) {
                             <option value="markdown">Markdown</option>
                             <option value="json">JSON</option>
                             <option value="xml">XML</option>
+                            <option value="diff">Diff</option>
+                        </select>
+                    </div>
+
+                    {/* Project Tree */}
+                    <div className="space-y-3">
+                        <label className="flex items-center gap-2 text-sm font-medium text-foreground">
+                            <input
+                                type="checkbox"
+                                checked={!!localConfig.project_tree}
+                                onChange={(e) => handleChange("project_tree", e.target.checked ? { max_depth: null, show_sizes: false } : null)}
+                            />
+                            <FolderTree size={16} className="text-muted-foreground" />
+                            Start with a Project Tree
+                        </label>
+                        {localConfig.project_tree && (
+                            <div className="flex items-center gap-3 pl-6">
+                                <input
+                                    type="number"
+                                    min="1"
+                                    value={localConfig.project_tree.max_depth ?? ""}
+                                    onChange={(e) => handleChange("project_tree", { ...localConfig.project_tree, max_depth: parseInt(e.target.value) || null })}
+                                    placeholder="All levels"
+                                    title="Collapse directories below this depth"
+                                    className="w-28 px-3 py-2 bg-secondary/30 border border-border rounded-lg text-sm focus:ring-2 focus:ring-primary/20 focus:border-primary outline-none transition-all"
+                                />
+                                <label className="flex items-center gap-2 text-sm text-foreground">
+                                    <input
+                                        type="checkbox"
+                                        checked={localConfig.project_tree.show_sizes}
+                                        onChange={(e) => handleChange("project_tree", { ...localConfig.project_tree, show_sizes: e.target.checked })}
+                                    />
+                                    Show file sizes
+                                </label>
+                            </div>
+                        )}
+                    </div>
+
+                    {/* Skeleton Mode */}
+                    <div className="space-y-1">
+                        <label className="flex items-center gap-2 text-sm font-medium text-foreground">
+                            <input
+                                type="checkbox"
+                                checked={!!localConfig.skeleton}
+                                onChange={(e) => handleChange("skeleton", e.target.checked)}
+                            />
+                            <Braces size={16} className="text-muted-foreground" />
+                            Skeleton Mode
+                        </label>
+                        <p className="text-xs text-muted-foreground pl-6">
+                            Copy signatures, types and doc comments with function bodies elided. Use the focus button on a selected file to keep it in full.
+                        </p>
+                    </div>
+
+                    {/* Git History */}
+                    <div className="space-y-3">
+                        <label className="text-sm font-medium flex items-center gap-2 text-foreground">
+                            <History size={16} className="text-muted-foreground" />
+                            Git History per File
+                        </label>
+                        <select
+                            value={localConfig.git_metadata || "off"}
+                            onChange={(e) => handleChange("git_metadata", e.target.value)}
+                            className="w-full px-3 py-2 bg-secondary/30 border border-border rounded-lg text-sm focus:ring-2 focus:ring-primary/20 focus:border-primary outline-none transition-all"
+                        >
+                            <option value="off">Off</option>
+                            <option value="last_commit">Last commit</option>
+                            <option value="blame">Last commit and line blame</option>
                         </select>
                     </div>
 
+                    {/* Diff Format */}
+                    {localConfig.diff && (
+                        <div className="space-y-3">
+                            <label className="text-sm font-medium flex items-center gap-2 text-foreground">
+                                <GitBranch size={16} className="text-muted-foreground" />
+                                Diff Format
+                            </label>
+                            <div className="flex gap-3">
+                                <input
+                                    value={localConfig.diff.base}
+                                    onChange={(e) => handleChange("diff", { ...localConfig.diff, base: e.target.value })}
+                                    placeholder="HEAD"
+                                    title="Ref to compare with"
+                                    className="flex-1 px-3 py-2 bg-secondary/30 border border-border rounded-lg text-sm font-mono focus:ring-2 focus:ring-primary/20 focus:border-primary outline-none transition-all"
+                                />
+                                <input
+                                    type="number"
+                                    min="0"
+                                    value={localConfig.diff.context_lines}
+                                    onChange={(e) => handleChange("diff", { ...localConfig.diff, context_lines: parseInt(e.target.value) || 0 })}
+                                    disabled={localConfig.diff.full_file}
+                                    title="Context lines around each change"
+                                    className="w-20 px-3 py-2 bg-secondary/30 border border-border rounded-lg text-sm focus:ring-2 focus:ring-primary/20 focus:border-primary outline-none transition-all disabled:opacity-50"
+                                />
+                            </div>
+                            <label className="flex items-center gap-2 text-sm text-foreground">
+                                <input
+                                    type="checkbox"
+                                    checked={localConfig.diff.full_file}
+                                    onChange={(e) => handleChange("diff", { ...localConfig.diff, full_file: e.target.checked })}
+                                />
+                                Show whole files with changes marked
+                            </label>
+                        </div>
+                    )}
+
                     {/* Max File Size */}
                     <div className="space-y-3">
                         <label className="text-sm font-medium flex items-center justify-between text-foreground">
@@ -139,6 +258,67 @@ export default function SettingsPanel({ config, onConfigChange, onClose }) {
                                 <div className="w-9 h-5 bg-secondary peer-focus:outline-none rounded-full peer peer-checked:after:translate-x-full peer-checked:after:border-white after:content-[''] after:absolute after:top-[2px] after:left-[2px] after:bg-white after:border-gray-300 after:border after:rounded-full after:h-4 after:w-4 after:transition-all peer-checked:bg-primary"></div>
                             </div>
                         </label>
+
+                        <div className="h-px bg-border/50 my-2" />
+
+                        <label className="flex items-center justify-between cursor-pointer group">
+                            <span className="text-sm font-medium flex items-center gap-2 text-foreground group-hover:text-primary transition-colors">
+                                <FolderTree size={16} className="text-muted-foreground" />
+                                Load Folders On Demand
+                            </span>
+                            <div className="relative inline-flex items-center cursor-pointer">
+                                <input
+                                    type="checkbox"
+                                    checked={localConfig.lazy_loading}
+                                    onChange={(e) => handleChange("lazy_loading", e.target.checked)}
+                                    className="sr-only peer"
+                                />
+                                <div className="w-9 h-5 bg-secondary peer-focus:outline-none rounded-full peer peer-checked:after:translate-x-full peer-checked:after:border-white after:content-[''] after:absolute after:top-[2px] after:left-[2px] after:bg-white after:border-gray-300 after:border after:rounded-full after:h-4 after:w-4 after:transition-all peer-checked:bg-primary"></div>
+                            </div>
+                        </label>
+                    </div>
+
+                    {/* Local HTTP API */}
+                    <div className="space-y-3">
+                        <label className="flex items-center justify-between cursor-pointer group">
+                            <span className="text-sm font-medium flex items-center gap-2 text-foreground group-hover:text-primary transition-colors">
+                                <Globe size={16} className="text-muted-foreground" />
+                                Local HTTP API
+                            </span>
+                            <div className="relative inline-flex items-center cursor-pointer">
+                                <input
+                                    type="checkbox"
+                                    checked={localConfig.http_api_enabled}
+                                    onChange={(e) => handleChange("http_api_enabled", e.target.checked)}
+                                    className="sr-only peer"
+                                />
+                                <div className="w-9 h-5 bg-secondary peer-focus:outline-none rounded-full peer peer-checked:after:translate-x-full peer-checked:after:border-white after:content-[''] after:absolute after:top-[2px] after:left-[2px] after:bg-white after:border-gray-300 after:border after:rounded-full after:h-4 after:w-4 after:transition-all peer-checked:bg-primary"></div>
+                            </div>
+                        </label>
+                        {localConfig.http_api_enabled && (
+                            <div className="space-y-2">
+                                <div className="flex items-center gap-2">
+                                    <span className="text-xs text-muted-foreground w-12">Port</span>
+                                    <input
+                                        type="number"
+                                        min="1"
+                                        max="65535"
+                                        value={localConfig.http_api_port}
+                                        onChange={(e) => handleChange("http_api_port", parseInt(e.target.value) || 7615)}
+                                        className="w-28 px-2 py-1 bg-secondary/30 border border-border rounded-md text-sm font-mono outline-none focus:border-primary"
+                                    />
+                                </div>
+                                <div className="flex items-center gap-2">
+                                    <span className="text-xs text-muted-foreground w-12">Token</span>
+                                    <code className="flex-1 px-2 py-1 bg-secondary/30 border border-border rounded-md text-xs font-mono truncate select-all">
+                                        {localConfig.http_api_token || "Generated when the API starts"}
+                                    </code>
+                                </div>
+                                <p className="text-xs text-muted-foreground">
+                                    Listens on 127.0.0.1 only. Send the token as <code>Authorization: Bearer &lt;token&gt;</code>.
+                                </p>
+                            </div>
+                        )}
                     </div>
 
                     {/* Custom Ignore Patterns */}
@@ -151,8 +331,62 @@ export default function SettingsPanel({ config, onConfigChange, onClose }) {
                             placeholder="*.log&#10;temp/"
                             className="w-full px-3 py-2 bg-secondary/30 border border-border rounded-lg text-sm font-mono focus:ring-2 focus:ring-primary/20 focus:border-primary outline-none transition-all resize-none"
                         />
-                        <p className="text-xs text-muted-foreground">Enter one pattern per line (e.g. *.log, temp/)</p>
+                        <p className="text-xs text-muted-foreground">One gitignore-style pattern per line (e.g. *.log, /build, src/**/*.snap, !keep.log)</p>
                     </div>
+
+                    {/* Allowed Paths */}
+                    <div className="space-y-3">
+                        <label className="text-sm font-medium text-foreground">Allowed Paths Outside the Workspace</label>
+                        <textarea
+                            value={(localConfig.allowed_paths || []).join("\n")}
+                            onChange={(e) => handleChange("allowed_paths", e.target.value.split("\n"))}
+                            rows={3}
+                            placeholder="/home/me/shared/prompts"
+                            className="w-full px-3 py-2 bg-secondary/30 border border-border rounded-lg text-sm font-mono focus:ring-2 focus:ring-primary/20 focus:border-primary outline-none transition-all resize-none"
+                        />
+                        <p className="text-xs text-muted-foreground">One absolute file or directory per line. Everything else outside the open folder is refused.</p>
+                    </div>
+
+                    {/* Secret Scanning */}
+                    {localConfig.secret_scan && (
+                        <div className="space-y-3">
+                            <label className="text-sm font-medium flex items-center gap-2 text-foreground">
+                                <ShieldAlert size={16} className="text-muted-foreground" />
+                                Secret Scanning
+                            </label>
+                            <select
+                                value={localConfig.secret_scan.mode}
+                                onChange={(e) => handleSecretScanChange("mode", e.target.value)}
+                                className="w-full px-3 py-2 bg-secondary/30 border border-border rounded-lg text-sm focus:ring-2 focus:ring-primary/20 focus:border-primary outline-none transition-all"
+                            >
+                                <option value="redact">Redact secrets</option>
+                                <option value="report">Report secrets</option>
+                                <option value="block">Ask before copying secrets</option>
+                                <option value="off">Off</option>
+                            </select>
+                            <div className="space-y-1">
+                                {localConfig.secret_scan.rules.map(rule => (
+                                    <label key={rule.id} className="flex items-center gap-2 text-sm text-foreground">
+                                        <input
+                                            type="checkbox"
+                                            checked={rule.enabled}
+                                            onChange={() => toggleSecretRule(rule.id)}
+                                        />
+                                        {rule.description}
+                                    </label>
+                                ))}
+                                <label className="flex items-center gap-2 text-sm text-foreground">
+                                    <input
+                                        type="checkbox"
+                                        checked={localConfig.secret_scan.entropy.enabled}
+                                        onChange={(e) => handleSecretScanChange("entropy", { ...localConfig.secret_scan.entropy, enabled: e.target.checked })}
+                                    />
+                                    Random-looking tokens
+                                </label>
+                            </div>
+                            <p className="text-xs text-muted-foreground">Custom rules can be added to config.json.</p>
+                        </div>
+                    )}
                 </div>
 
                 <div className="flex justify-between items-center px-6 py-4 border-t border-border bg-secondary/20">
@@ -14,6 +14,7 @@ import "prismjs/components/prism-json";
 import "prismjs/components/prism-markdown";
 import "prismjs/components/prism-css";
 import "prismjs/components/prism-bash";
+import "prismjs/components/prism-diff";
 
 export default function SyntaxHighlighter({ code, language }) {
     useEffect(() => {
@@ -34,7 +35,8 @@ export default function SyntaxHighlighter({ code, language }) {
             "md": "markdown",
             "css": "css",
             "sh": "bash",
-            "bash": "bash"
+            "bash": "bash",
+            "diff": "diff"
         };
         return langMap[language] || "javascript";
     };
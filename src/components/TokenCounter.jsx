@@ -37,9 +37,38 @@ export default function TokenCounter({ content }) {
 
     if (!tokenEstimate) return null;
 
+    // Warn on the model whose context window fills up first
+    const fullest = tokenEstimate.models.reduce(
+        (max, model) => (!max || model.context_used_percent > max.context_used_percent ? model : max),
+        null
+    );
+    const isLarge = fullest && fullest.context_used_percent > 100;
+    const isWarning = fullest && fullest.context_used_percent > 50;
+
+    return () => document.removeEventListener("mousedown", handleClickOutside);
+    }, []);
+
+    async function estimateTokens() {
+        try {
+            const estimate = await invoke("estimate_file_tokens", { content });
+            setTokenEstimate(estimate);
+        } catch (error) {
+            console.error("Token estimation failed:", error);
+        }
+    }
+
+    if (!tokenEstimate) return null;
+
     const isLarge = tokenEstimate.gpt4_estimate > 100000;
     const isWarning = tokenEstimate.gpt4_estimate > 50000;
 
+    const models = [
+        { label: "GPT-4", count: tokenEstimate.gpt4_estimate, exact: tokenEstimate.gpt4_exact },
+        { label: "GPT-4o", count: tokenEstimate.gpt4o_estimate, exact: tokenEstimate.gpt4o_exact },
+        { label: "Claude", count: tokenEstimate.claude_estimate, exact: tokenEstimate.claude_exact },
+        { label: "Gemini", count: tokenEstimate.gemini_estimate, exact: tokenEstimate.gemini_exact },
+    ];
+
     return (
         <div className="relative" ref={popoverRef}>
             <button
@@ -55,7 +84,7 @@ export default function TokenCounter({ content }) {
                 onClick={() => setShowPopover(!showPopover)}
             >
                 <Calculator size={14} />
-                <span>{tokenEstimate.gpt4_estimate.toLocaleString()} tokens</span>
+                <span>{tokenEstimate.total_tokens.toLocaleString()} tokens</span>
             </button>
 
             {showPopover && (
@@ -71,19 +100,26 @@ export default function TokenCounter({ content }) {
                     </div>
 
                     <div className="p-3 space-y-3">
-                        <div className="grid grid-cols-3 gap-3">
-                            <div className="flex flex-col">
-                                <span className="text-[10px] text-muted-foreground uppercase">GPT-4</span>
-                                <span className="text-sm font-mono font-medium">{tokenEstimate.gpt4_estimate.toLocaleString()}</span>
-                            </div>
-                            <div className="flex flex-col">
-                                <span className="text-[10px] text-muted-foreground uppercase">Claude</span>
-                                <span className="text-sm font-mono font-medium">{tokenEstimate.claude_estimate.toLocaleString()}</span>
-                            </div>
-                            <div className="flex flex-col">
-                                <span className="text-[10px] text-muted-foreground uppercase">Gemini</span>
-                                <span className="text-sm font-mono font-medium">{tokenEstimate.gemini_estimate.toLocaleString()}</span>
-                            </div>
+                        <div className="space-y-2">
+                            {tokenEstimate.models.map((model) => (
+                                <div key={model.model} className="flex items-center justify-between gap-2">
+                                    <div className="flex flex-col min-w-0">
+                                        <span className="text-[10px] text-muted-foreground uppercase truncate" title={model.tokenizer}>
+                                            {model.name}
+                                        </span>
+                                        <span
+                                            className="text-sm font-mono font-medium"
+                                            title={model.exact ? "Exact count" : "Approximate count"}
+                                        >
+                                            {model.exact ? "" : "≈"}{model.tokens.toLocaleString()}
+                                        </span>
+                                    </div>
+                                    <div className="flex flex-col items-end text-[10px] font-mono text-muted-foreground">
+                                        <span>{model.context_used_percent.toFixed(1)}% of {model.context_window.toLocaleString()}</span>
+                                        <span>${model.cost.toFixed(4)}</span>
+                                    </div>
+                                </div>
+                            ))}
                         </div>
 
                         <div className="grid grid-cols-3 gap-2 pt-2 border-t border-border/50">
@@ -104,13 +140,13 @@ export default function TokenCounter({ content }) {
                         {isLarge && (
                             <div className="flex items-start gap-2 text-[10px] text-destructive bg-destructive/10 p-2 rounded">
                                 <AlertCircle size={12} className="shrink-0 mt-0.5" />
-                                <span>Very large! May exceed limits</span>
+                                <span>Exceeds the {fullest.name} context window</span>
                             </div>
                         )}
                         {isWarning && !isLarge && (
                             <div className="flex items-start gap-2 text-[10px] text-yellow-500 bg-yellow-500/10 p-2 rounded">
                                 <AlertTriangle size={12} className="shrink-0 mt-0.5" />
-                                <span>Large content</span>
+                                <span>Uses over half of the {fullest.name} context window</span>
                             </div>
                         )}
                     </div>
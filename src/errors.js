@@ -0,0 +1,20 @@
+// Backend commands reject with { kind, path, io_kind, message }
+
+const HINTS = {
+  not_found: "It may have been moved or deleted.",
+  permission_denied: "Check the file permissions or grant FileSurfer access to this folder.",
+  outside_workspace: "Open its folder, or add it under Allowed Paths in Settings.",
+  not_a_git_repo: "Run `git init` in this folder or turn off Git-only mode.",
+  secrets_found: "Remove them, or change Secret Scanning in Settings.",
+};
+
+export function errorMessage(error) {
+  if (typeof error === "string") return error;
+  return error?.message ?? String(error);
+}
+
+// The message followed by a suggested fix for the error's kind, if there is one
+export function describeError(error) {
+  const hint = HINTS[error?.kind];
+  return hint ? `${errorMessage(error)}. ${hint}` : errorMessage(error);
+}